@@ -0,0 +1,418 @@
+use serde::{Deserialize, Serialize};
+
+use super::onsets::OnsetEnvelope;
+
+/// Tempo search range.
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 200.0;
+/// Centre and width (octaves) of the tempo prior; halves octave errors.
+const PRIOR_BPM: f64 = 120.0;
+const PRIOR_OCTAVES: f64 = 1.0;
+/// Penalty for beat spacing that strays from the global period, per squared
+/// log-ratio. Higher keeps the tracker closer to a constant tempo.
+const TIGHTNESS: f64 = 100.0;
+/// Closest two warp markers may get, in seconds.
+const MIN_WARP_GAP: f64 = 0.01;
+
+/// Beat positions for the whole file. Live recordings drift, so every beat has
+/// its own time; warp markers pin individual beats and re-time the beats
+/// between them. Outside the tracked beats the edge tempo is extended.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SavedTempoMap", into = "SavedTempoMap")]
+pub struct TempoMap {
+    /// Beat times from tracking (or a constant grid), ascending, at least two.
+    tracked: Vec<f64>,
+    /// Warp markers as (slot in `tracked`, pinned time), sorted by slot.
+    warps: Vec<(usize, f64)>,
+    /// `tracked` re-timed through the warp markers.
+    beats: Vec<f64>,
+    /// Slot of bar 1, beat 1.
+    downbeat: usize,
+    pub beats_per_bar: u32,
+}
+
+impl TempoMap {
+    fn new(tracked: Vec<f64>, downbeat: usize, beats_per_bar: u32) -> Self {
+        debug_assert!(tracked.len() >= 2);
+        Self {
+            beats: tracked.clone(),
+            tracked,
+            warps: Vec::new(),
+            downbeat,
+            beats_per_bar,
+        }
+    }
+
+    /// Evenly spaced beats covering `[0, duration]` with bar 1 at `downbeat`.
+    pub fn constant(bpm: f64, downbeat: f64, beats_per_bar: u32, duration: f64) -> Self {
+        let period = 60.0 / bpm.max(1.0);
+        let downbeat = downbeat.max(0.0);
+        let lead = (downbeat / period).floor() as usize;
+        let first = downbeat - lead as f64 * period;
+        let count = (((duration - first) / period).ceil() as usize + 1).max(lead + 2);
+        let tracked = (0..count).map(|k| first + k as f64 * period).collect();
+        Self::new(tracked, lead, beats_per_bar)
+    }
+
+    /// Time of bar 1, beat 1.
+    pub fn downbeat_time(&self) -> f64 {
+        self.beats[self.downbeat]
+    }
+
+    /// Beat index at `t`, fractional; beat 0 is the downbeat.
+    pub fn beat_at(&self, t: f64) -> f64 {
+        let n = self.beats.len();
+        let slot = if t < self.beats[0] {
+            (t - self.beats[0]) / (self.beats[1] - self.beats[0])
+        } else if t >= self.beats[n - 1] {
+            (n - 1) as f64 + (t - self.beats[n - 1]) / (self.beats[n - 1] - self.beats[n - 2])
+        } else {
+            let k = self.beats.partition_point(|b| *b <= t) - 1;
+            k as f64 + (t - self.beats[k]) / (self.beats[k + 1] - self.beats[k])
+        };
+        slot - self.downbeat as f64
+    }
+
+    pub fn time_of_beat(&self, beat: i64) -> f64 {
+        let n = self.beats.len() as i64;
+        let slot = beat + self.downbeat as i64;
+        if slot < 0 {
+            self.beats[0] + slot as f64 * (self.beats[1] - self.beats[0])
+        } else if slot >= n {
+            let last = self.beats[n as usize - 1];
+            let period = last - self.beats[n as usize - 2];
+            last + (slot - n + 1) as f64 * period
+        } else {
+            self.beats[slot as usize]
+        }
+    }
+
+    /// Start of a 1-based bar.
+    pub fn time_of_bar(&self, bar: i64) -> f64 {
+        self.time_of_beat((bar - 1) * self.beats_per_bar.max(1) as i64)
+    }
+
+    /// Length of the beat containing `t`, in seconds.
+    pub fn beat_seconds_at(&self, t: f64) -> f64 {
+        let i = self.beat_at(t).floor() as i64;
+        self.time_of_beat(i + 1) - self.time_of_beat(i)
+    }
+
+    pub fn bpm_at(&self, t: f64) -> f64 {
+        60.0 / self.beat_seconds_at(t)
+    }
+
+    /// Average tempo over the tracked beats.
+    pub fn mean_bpm(&self) -> f64 {
+        let n = self.beats.len();
+        60.0 * (n - 1) as f64 / (self.beats[n - 1] - self.beats[0])
+    }
+
+    /// Whole beats in `[t0, t1]` as (index, seconds).
+    pub fn beats_in(&self, t0: f64, t1: f64) -> impl Iterator<Item = (i64, f64)> + '_ {
+        let first = self.beat_at(t0).ceil() as i64;
+        let last = self.beat_at(t1).floor() as i64;
+        (first..=last).map(|i| (i, self.time_of_beat(i)))
+    }
+
+    /// 1-based (bar, beat) for a beat index; pickup beats fall in bar 0 or below.
+    pub fn bar_beat(&self, beat: i64) -> (i64, u32) {
+        let bpb = self.beats_per_bar.max(1) as i64;
+        (beat.div_euclid(bpb) + 1, beat.rem_euclid(bpb) as u32 + 1)
+    }
+
+    /// Make the beat nearest `t` bar 1, beat 1 without moving any beat.
+    pub fn set_downbeat_near(&mut self, t: f64) {
+        let slot = self.beat_at(t).round() as i64 + self.downbeat as i64;
+        self.downbeat = slot.clamp(0, self.beats.len() as i64 - 1) as usize;
+    }
+
+    /// Whether `beat` can carry a warp marker (it lies on a tracked beat).
+    pub fn is_warpable(&self, beat: i64) -> bool {
+        let slot = beat + self.downbeat as i64;
+        slot >= 0 && slot < self.beats.len() as i64
+    }
+
+    /// Pin `beat` to `t`. Beats between neighbouring markers are stretched to
+    /// fit; beats beyond the outermost markers move with them.
+    pub fn warp(&mut self, beat: i64, t: f64) {
+        if !self.is_warpable(beat) {
+            return;
+        }
+        let slot = (beat + self.downbeat as i64) as usize;
+        let pos = self.warps.partition_point(|(s, _)| *s < slot);
+        let replace = self.warps.get(pos).is_some_and(|(s, _)| *s == slot);
+        let lo = pos
+            .checked_sub(1)
+            .map(|p| self.warps[p].1 + MIN_WARP_GAP)
+            .unwrap_or(f64::NEG_INFINITY);
+        let next = if replace { pos + 1 } else { pos };
+        let hi = self
+            .warps
+            .get(next)
+            .map(|(_, t)| t - MIN_WARP_GAP)
+            .unwrap_or(f64::INFINITY);
+        let t = t.clamp(lo, hi.max(lo));
+        if replace {
+            self.warps[pos].1 = t;
+        } else {
+            self.warps.insert(pos, (slot, t));
+        }
+        self.retime();
+    }
+
+    pub fn clear_warps(&mut self) {
+        self.warps.clear();
+        self.retime();
+    }
+
+    pub fn has_warps(&self) -> bool {
+        !self.warps.is_empty()
+    }
+
+    /// Warp markers as (beat index, seconds).
+    pub fn warps(&self) -> impl Iterator<Item = (i64, f64)> + '_ {
+        self.warps
+            .iter()
+            .map(|(s, t)| (*s as i64 - self.downbeat as i64, *t))
+    }
+
+    fn retime(&mut self) {
+        self.beats.clone_from(&self.tracked);
+        let (Some(&(first, t_first)), Some(&(last, t_last))) =
+            (self.warps.first(), self.warps.last())
+        else {
+            return;
+        };
+        let shift = t_first - self.tracked[first];
+        for b in &mut self.beats[..first] {
+            *b += shift;
+        }
+        let shift = t_last - self.tracked[last];
+        for b in &mut self.beats[last..] {
+            *b += shift;
+        }
+        for w in self.warps.windows(2) {
+            let ((sa, ta), (sb, tb)) = (w[0], w[1]);
+            let scale = (tb - ta) / (self.tracked[sb] - self.tracked[sa]);
+            for k in sa..=sb {
+                self.beats[k] = ta + (self.tracked[k] - self.tracked[sa]) * scale;
+            }
+        }
+    }
+}
+
+/// What is saved of a tempo map; the warped beats are recomputed on load.
+#[derive(Serialize, Deserialize)]
+struct SavedTempoMap {
+    tracked: Vec<f64>,
+    warps: Vec<(usize, f64)>,
+    downbeat: usize,
+    beats_per_bar: u32,
+}
+
+impl From<TempoMap> for SavedTempoMap {
+    fn from(map: TempoMap) -> Self {
+        Self {
+            tracked: map.tracked,
+            warps: map.warps,
+            downbeat: map.downbeat,
+            beats_per_bar: map.beats_per_bar,
+        }
+    }
+}
+
+impl TryFrom<SavedTempoMap> for TempoMap {
+    type Error = String;
+
+    fn try_from(saved: SavedTempoMap) -> Result<Self, Self::Error> {
+        let n = saved.tracked.len();
+        if n < 2 || saved.tracked.windows(2).any(|w| w[1] <= w[0]) {
+            return Err("tempo map needs at least two ascending beats".into());
+        }
+        if saved.downbeat >= n || saved.warps.iter().any(|(slot, _)| *slot >= n) {
+            return Err("tempo map refers to a beat it does not have".into());
+        }
+        if saved
+            .warps
+            .windows(2)
+            .any(|w| w[1].0 <= w[0].0 || w[1].1 <= w[0].1)
+        {
+            return Err("tempo map warp markers are out of order".into());
+        }
+        let mut map = TempoMap::new(saved.tracked, saved.downbeat, saved.beats_per_bar.max(1));
+        map.warps = saved.warps;
+        map.retime();
+        Ok(map)
+    }
+}
+
+/// Track beats through an onset envelope, letting the tempo drift.
+/// The global period comes from the weighted autocorrelation; dynamic
+/// programming then picks the beat sequence that lands on the most onset energy
+/// while keeping each spacing close to that period. The downbeat is the
+/// strongest beat of the bar (assuming `beats_per_bar` beats per bar).
+pub fn estimate_tempo_map(env: &OnsetEnvelope, beats_per_bar: u32) -> Option<TempoMap> {
+    let period = estimate_period(env)?;
+    let frames = track_beats(&env.values, period);
+    if frames.len() < 2 {
+        return None;
+    }
+
+    let o = &env.values;
+    let bpb = beats_per_bar.max(1) as usize;
+    let strength = |phase: usize| -> f64 {
+        frames
+            .iter()
+            .skip(phase)
+            .step_by(bpb)
+            .map(|f| o[(f.round() as usize).min(o.len() - 1)] as f64)
+            .sum()
+    };
+    let downbeat = (0..bpb.min(frames.len()))
+        .max_by(|a, b| strength(*a).total_cmp(&strength(*b)))
+        .unwrap_or(0);
+
+    let fr = env.frame_rate();
+    let tracked = frames.iter().map(|f| f / fr).collect();
+    Some(TempoMap::new(tracked, downbeat, beats_per_bar))
+}
+
+/// Dynamic-programming beat tracker over `o` with a target spacing of
+/// `period` frames. Returns sub-frame beat positions, ascending.
+fn track_beats(o: &[f32], period: f64) -> Vec<f64> {
+    let n = o.len();
+    let mean = o.iter().map(|v| *v as f64).sum::<f64>() / n.max(1) as f64;
+    let var = o.iter().map(|v| (*v as f64 - mean).powi(2)).sum::<f64>() / n.max(1) as f64;
+    if n == 0 || var <= 0.0 {
+        return Vec::new();
+    }
+    let norm = 1.0 / var.sqrt();
+
+    let min_gap = (period * 0.5).round().max(1.0) as usize;
+    let max_gap = (period * 2.0).round() as usize;
+    let mut score = vec![0.0f64; n];
+    let mut back: Vec<Option<usize>> = vec![None; n];
+    for t in 0..n {
+        let mut best: Option<(f64, usize)> = None;
+        for gap in min_gap..=max_gap.min(t) {
+            let p = t - gap;
+            let ratio = (gap as f64 / period).ln();
+            let s = score[p] - TIGHTNESS * ratio * ratio;
+            if best.is_none_or(|(b, _)| s > b) {
+                best = Some((s, p));
+            }
+        }
+        let local = o[t] as f64 * norm;
+        match best {
+            Some((s, p)) if s > 0.0 => {
+                score[t] = local + s;
+                back[t] = Some(p);
+            }
+            _ => score[t] = local,
+        }
+    }
+
+    // End on the best-scoring frame within the last period.
+    let tail = n.saturating_sub(period.ceil() as usize);
+    let Some(mut t) = (tail..n).max_by(|a, b| score[*a].total_cmp(&score[*b])) else {
+        return Vec::new();
+    };
+    let mut frames = vec![t];
+    while let Some(p) = back[t] {
+        frames.push(p);
+        t = p;
+    }
+    frames.reverse();
+
+    // Parabolic refinement on local peaks of the envelope.
+    frames
+        .into_iter()
+        .map(|t| {
+            if t == 0 || t + 1 >= n {
+                return t as f64;
+            }
+            let (y0, y1, y2) = (o[t - 1] as f64, o[t] as f64, o[t + 1] as f64);
+            let denom = y0 - 2.0 * y1 + y2;
+            if y1 >= y0 && y1 >= y2 && denom.abs() > f64::EPSILON {
+                t as f64 + (0.5 * (y0 - y2) / denom).clamp(-0.5, 0.5)
+            } else {
+                t as f64
+            }
+        })
+        .collect()
+}
+
+/// Global beat period in envelope frames: weighted autocorrelation, then a
+/// joint period/phase refinement against a constant grid.
+fn estimate_period(env: &OnsetEnvelope) -> Option<f64> {
+    let fr = env.frame_rate();
+    let o = &env.values;
+    let min_lag = (60.0 / MAX_BPM * fr).floor() as usize;
+    let max_lag = (60.0 / MIN_BPM * fr).ceil() as usize;
+    if min_lag < 1 || o.len() < max_lag * 4 {
+        return None;
+    }
+
+    let acf: Vec<f64> = (0..=max_lag + 1)
+        .map(|lag| {
+            o.iter()
+                .zip(&o[lag..])
+                .map(|(a, b)| *a as f64 * *b as f64)
+                .sum()
+        })
+        .collect();
+    let weight = |lag: f64| {
+        let bpm = 60.0 * fr / lag;
+        let x = (bpm / PRIOR_BPM).log2() / PRIOR_OCTAVES;
+        (-0.5 * x * x).exp()
+    };
+    let best = (min_lag..=max_lag).max_by(|a, b| {
+        let wa = acf[*a] * weight(*a as f64);
+        let wb = acf[*b] * weight(*b as f64);
+        wa.total_cmp(&wb)
+    })?;
+    if acf[best] <= 0.0 {
+        return None;
+    }
+
+    // Parabolic interpolation for a sub-frame period.
+    let (y0, y1, y2) = (acf[best - 1], acf[best], acf[best + 1]);
+    let denom = y0 - 2.0 * y1 + y2;
+    let shift = if denom.abs() > f64::EPSILON {
+        (0.5 * (y0 - y2) / denom).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+    let coarse = best as f64 + shift;
+
+    // Energy collected by a grid starting at `offset` frames with `stride` spacing.
+    let grid_energy = |offset: f64, stride: f64| {
+        let mut sum = 0.0f64;
+        let mut t = offset;
+        while t + 1.0 < o.len() as f64 {
+            let i = t as usize;
+            let frac = (t - i as f64) as f32;
+            sum += (o[i] + (o[i + 1] - o[i]) * frac) as f64;
+            t += stride;
+        }
+        sum
+    };
+
+    // Refine period and phase together: a small period error adds up over a long
+    // file, and the grid has to stay on the beat to the end.
+    let mut best_fit = (f64::NEG_INFINITY, coarse, 0.0);
+    for step in -20..=20 {
+        let period = coarse * (1.0 + step as f64 * 0.0005);
+        let phase_steps = (period * 2.0).ceil() as usize;
+        for s in 0..phase_steps {
+            let phase = s as f64 * 0.5;
+            let e = grid_energy(phase, period);
+            if e > best_fit.0 {
+                best_fit = (e, period, phase);
+            }
+        }
+    }
+    let (_, period, _) = best_fit;
+    Some(period)
+}
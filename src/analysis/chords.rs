@@ -0,0 +1,279 @@
+use super::beats::TempoMap;
+use super::notes::{midi_from_hz, pitch_class_name};
+use super::stft::Stft;
+
+/// Long window: bass notes a semitone apart need bins a few Hz wide.
+const FFT_SIZE: usize = 8192;
+const HOP: usize = 2048;
+/// Frequency range folded into the chroma vector.
+const MIN_HZ: f64 = 60.0;
+const MAX_HZ: f64 = 2000.0;
+/// Segment length when no tempo map is available.
+const FALLBACK_SEGMENT_SECONDS: f64 = 0.5;
+/// Segments quieter than this fraction of the loudest get no chord.
+const SILENCE_RATIO: f32 = 0.05;
+/// A new chord must fit this much better than the current one to replace it.
+const CHANGE_MARGIN: f32 = 0.03;
+
+/// Krumhansl–Kessler key profiles, tonic first.
+const MAJOR_PROFILE: [f32; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_PROFILE: [f32; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    Major,
+    Minor,
+}
+
+/// A triad, or a key when used as tonic + mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chord {
+    /// Pitch class of the root, 0 = C.
+    pub root: i32,
+    pub quality: Quality,
+}
+
+impl Chord {
+    pub fn transposed(self, semitones: i32) -> Self {
+        Self {
+            root: (self.root + semitones).rem_euclid(12),
+            quality: self.quality,
+        }
+    }
+
+    /// Lead-sheet symbol, e.g. "Bb" or "F#m".
+    pub fn symbol(&self) -> String {
+        match self.quality {
+            Quality::Major => pitch_class_name(self.root).to_string(),
+            Quality::Minor => format!("{}m", pitch_class_name(self.root)),
+        }
+    }
+
+    /// Key name, e.g. "A minor".
+    pub fn key_name(&self) -> String {
+        match self.quality {
+            Quality::Major => format!("{} major", pitch_class_name(self.root)),
+            Quality::Minor => format!("{} minor", pitch_class_name(self.root)),
+        }
+    }
+}
+
+/// One chord held from `start` to `end`; `None` where nothing harmonic plays.
+#[derive(Debug, Clone)]
+pub struct ChordSpan {
+    pub start: f64,
+    pub end: f64,
+    pub chord: Option<Chord>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChordTimeline {
+    pub key: Chord,
+    /// Consecutive, non-overlapping spans covering the file.
+    pub spans: Vec<ChordSpan>,
+}
+
+/// Average chroma over one beat (or fallback segment).
+#[derive(Debug, Clone, Copy)]
+pub struct ChromaSegment {
+    pub start: f64,
+    pub end: f64,
+    pub chroma: [f32; 12],
+}
+
+/// Chroma averaged per beat of `map`; without one, fixed half-second segments
+/// are used. Shared by chord and section analysis.
+pub fn beat_chroma(mono: &[f32], sample_rate: u32, map: Option<&TempoMap>) -> Vec<ChromaSegment> {
+    let chroma = chromagram(mono, sample_rate);
+    if chroma.is_empty() {
+        return Vec::new();
+    }
+    let frame_seconds = HOP as f64 / sample_rate as f64;
+    let duration = mono.len() as f64 / sample_rate as f64;
+
+    let mut bounds = vec![0.0];
+    match map {
+        Some(map) => bounds.extend(map.beats_in(0.0, duration).map(|(_, t)| t)),
+        None => {
+            let mut t = FALLBACK_SEGMENT_SECONDS;
+            while t < duration {
+                bounds.push(t);
+                t += FALLBACK_SEGMENT_SECONDS;
+            }
+        }
+    }
+    bounds.push(duration);
+    bounds.dedup_by(|b, a| *b - *a < frame_seconds);
+
+    bounds
+        .windows(2)
+        .map(|w| {
+            let k0 = ((w[0] / frame_seconds).round() as usize).min(chroma.len() - 1);
+            let k1 = ((w[1] / frame_seconds).round() as usize).clamp(k0 + 1, chroma.len());
+            let mut sum = [0.0f32; 12];
+            for frame in &chroma[k0..k1] {
+                for (s, v) in sum.iter_mut().zip(frame) {
+                    *s += v;
+                }
+            }
+            let n = (k1 - k0) as f32;
+            ChromaSegment {
+                start: w[0],
+                end: w[1],
+                chroma: sum.map(|s| s / n),
+            }
+        })
+        .collect()
+}
+
+/// Estimate the global key and one chord per segment from `beat_chroma` output.
+pub fn analyze(segments: &[ChromaSegment]) -> Option<ChordTimeline> {
+    let loudest = segments
+        .iter()
+        .map(|seg| seg.chroma.iter().sum::<f32>())
+        .fold(0.0f32, f32::max);
+    if loudest <= 0.0 {
+        return None;
+    }
+
+    let mut spans: Vec<ChordSpan> = Vec::new();
+    let mut current: Option<Chord> = None;
+    for ChromaSegment {
+        start,
+        end,
+        chroma: c,
+    } in segments
+    {
+        let chord = if c.iter().sum::<f32>() < loudest * SILENCE_RATIO {
+            None
+        } else {
+            let (best, best_score) = best_chord(c);
+            // Hold the current chord unless the new one is clearly better.
+            match current {
+                Some(cur) if triad_score(c, cur) + CHANGE_MARGIN >= best_score => Some(cur),
+                _ => Some(best),
+            }
+        };
+        current = chord;
+        match spans.last_mut() {
+            Some(last) if last.chord == chord => last.end = *end,
+            _ => spans.push(ChordSpan {
+                start: *start,
+                end: *end,
+                chord,
+            }),
+        }
+    }
+
+    let mut total = [0.0f32; 12];
+    for seg in segments {
+        for (t, v) in total.iter_mut().zip(&seg.chroma) {
+            *t += v;
+        }
+    }
+    Some(ChordTimeline {
+        key: estimate_key(&total),
+        spans,
+    })
+}
+
+/// Pitch-class energy per STFT frame.
+fn chromagram(mono: &[f32], sample_rate: u32) -> Vec<[f32; 12]> {
+    let mut stft = Stft::new(FFT_SIZE, HOP);
+    let pitch_class: Vec<Option<usize>> = (0..stft.bins())
+        .map(|bin| {
+            let hz = stft.bin_hz(bin as f64, sample_rate);
+            (MIN_HZ..=MAX_HZ)
+                .contains(&hz)
+                .then(|| (midi_from_hz(hz).round() as i64).rem_euclid(12) as usize)
+        })
+        .collect();
+
+    let mut mags = Vec::with_capacity(stft.bins());
+    (0..stft.frame_count(mono.len()))
+        .map(|k| {
+            stft.magnitudes(mono, k, &mut mags);
+            let mut chroma = [0.0f32; 12];
+            for (m, pc) in mags.iter().zip(&pitch_class) {
+                if let Some(pc) = pc {
+                    chroma[*pc] += m * m;
+                }
+            }
+            // Square root tames the loudest partials so triads still register.
+            chroma.map(f32::sqrt)
+        })
+        .collect()
+}
+
+/// Cosine similarity between a chroma vector and a triad template.
+fn triad_score(chroma: &[f32; 12], chord: Chord) -> f32 {
+    let third = match chord.quality {
+        Quality::Major => 4,
+        Quality::Minor => 3,
+    };
+    let norm = chroma.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm <= 0.0 {
+        return 0.0;
+    }
+    let at = |i: i32| chroma[(chord.root + i).rem_euclid(12) as usize];
+    (at(0) + at(third) + at(7)) / (norm * 3f32.sqrt())
+}
+
+fn best_chord(chroma: &[f32; 12]) -> (Chord, f32) {
+    (0..12)
+        .flat_map(|root| [Quality::Major, Quality::Minor].map(|quality| Chord { root, quality }))
+        .map(|chord| (chord, triad_score(chroma, chord)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((
+            Chord {
+                root: 0,
+                quality: Quality::Major,
+            },
+            0.0,
+        ))
+}
+
+/// Key whose rotated profile correlates best with the overall chroma.
+fn estimate_key(chroma: &[f32; 12]) -> Chord {
+    let mut best = (
+        Chord {
+            root: 0,
+            quality: Quality::Major,
+        },
+        f32::NEG_INFINITY,
+    );
+    for (quality, profile) in [
+        (Quality::Major, &MAJOR_PROFILE),
+        (Quality::Minor, &MINOR_PROFILE),
+    ] {
+        for root in 0..12 {
+            let rotated: [f32; 12] =
+                std::array::from_fn(|i| profile[(i + 12 - root as usize) % 12]);
+            let r = correlation(chroma, &rotated);
+            if r > best.1 {
+                best = (Chord { root, quality }, r);
+            }
+        }
+    }
+    best.0
+}
+
+fn correlation(a: &[f32; 12], b: &[f32; 12]) -> f32 {
+    let ma = a.iter().sum::<f32>() / 12.0;
+    let mb = b.iter().sum::<f32>() / 12.0;
+    let (mut num, mut da, mut db) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        num += (x - ma) * (y - mb);
+        da += (x - ma) * (x - ma);
+        db += (y - mb) * (y - mb);
+    }
+    if da <= 0.0 || db <= 0.0 {
+        0.0
+    } else {
+        num / (da * db).sqrt()
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use symphonia::core::dsp::complex::Complex;
+
+use super::stft::{Istft, Stft};
+use crate::audio::decode::MemoryAudio;
+
+const FFT_SIZE: usize = 2048;
+const HOP: usize = 512;
+/// Share of the quietest frames averaged into an automatic noise profile.
+const QUIET_FRACTION: f64 = 0.1;
+/// Gain never drops below this, so residual noise stays smooth instead of
+/// breaking into "musical noise" chirps.
+const GAIN_FLOOR: f32 = 0.1;
+/// Weight of the previous frame's gain, smoothing gains over time.
+const GAIN_SMOOTHING: f32 = 0.5;
+
+/// Where the noise spectrum is learnt from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseSource {
+    /// The quietest frames of the whole file.
+    Auto,
+    /// A region (seconds) that holds only noise, e.g. a gap between takes.
+    Region(f64, f64),
+}
+
+/// Spectral-subtraction denoise. `strength` scales the subtracted noise
+/// spectrum: 1 removes the learnt level, higher values also catch
+/// fluctuations above it at the cost of some thinning.
+pub fn denoise(audio: &MemoryAudio, source: NoiseSource, strength: f32) -> Arc<MemoryAudio> {
+    let ch = audio.channels as usize;
+    let frames = audio.frames as usize;
+    let mut data = vec![0.0f32; frames * ch];
+    for c in 0..ch {
+        let signal: Vec<f32> = audio.data.iter().skip(c).step_by(ch).copied().collect();
+        let profile = match source {
+            NoiseSource::Auto => quiet_profile(&signal),
+            NoiseSource::Region(t0, t1) => {
+                let sr = audio.sample_rate as f64;
+                region_profile(&signal, (t0 * sr) as usize, (t1 * sr) as usize)
+            }
+        };
+        for (f, s) in subtract(&signal, &profile, strength)
+            .into_iter()
+            .enumerate()
+        {
+            data[f * ch + c] = s;
+        }
+    }
+    Arc::new(MemoryAudio {
+        sample_rate: audio.sample_rate,
+        channels: audio.channels,
+        frames: audio.frames,
+        data,
+    })
+}
+
+/// Mean magnitude spectrum of the frames centered within `[start, end)` samples.
+fn region_profile(signal: &[f32], start: usize, end: usize) -> Vec<f32> {
+    let mut stft = Stft::new(FFT_SIZE, HOP);
+    let k0 = start.div_ceil(HOP);
+    let k1 = (end / HOP).max(k0 + 1).min(stft.frame_count(signal.len()));
+    let mut sum = vec![0.0f32; stft.bins()];
+    let mut mags = Vec::with_capacity(stft.bins());
+    for k in k0..k1 {
+        stft.magnitudes(signal, k, &mut mags);
+        for (s, m) in sum.iter_mut().zip(&mags) {
+            *s += m;
+        }
+    }
+    let n = k1.saturating_sub(k0).max(1) as f32;
+    sum.into_iter().map(|s| s / n).collect()
+}
+
+/// Mean magnitude spectrum of the quietest frames, skipping digital silence.
+fn quiet_profile(signal: &[f32]) -> Vec<f32> {
+    let mut stft = Stft::new(FFT_SIZE, HOP);
+    let count = stft.frame_count(signal.len());
+    let mut mags = Vec::with_capacity(stft.bins());
+    let mut energies: Vec<(f32, usize)> = (0..count)
+        .filter_map(|k| {
+            stft.magnitudes(signal, k, &mut mags);
+            let energy = mags.iter().map(|m| m * m).sum::<f32>();
+            (energy > 0.0).then_some((energy, k))
+        })
+        .collect();
+    energies.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let take = ((energies.len() as f64 * QUIET_FRACTION).ceil() as usize).max(1);
+
+    let mut sum = vec![0.0f32; stft.bins()];
+    for &(_, k) in energies.iter().take(take) {
+        stft.magnitudes(signal, k, &mut mags);
+        for (s, m) in sum.iter_mut().zip(&mags) {
+            *s += m;
+        }
+    }
+    let n = take.min(energies.len()).max(1) as f32;
+    sum.into_iter().map(|s| s / n).collect()
+}
+
+fn subtract(signal: &[f32], profile: &[f32], strength: f32) -> Vec<f32> {
+    let mut stft = Stft::new(FFT_SIZE, HOP);
+    let mut out = Istft::new(FFT_SIZE, HOP, signal.len());
+    // `profile` is in `Stft::magnitudes` units; raw spectra are N/4 larger.
+    let scale = FFT_SIZE as f32 / 4.0;
+    let mut gains = vec![1.0f32; stft.bins()];
+    let mut cleaned = vec![Complex::default(); stft.bins()];
+    for k in 0..stft.frame_count(signal.len()) {
+        let spectrum = stft.spectrum(signal, k);
+        for (b, s) in spectrum.iter().enumerate() {
+            let mag = (s.re * s.re + s.im * s.im).sqrt();
+            let noise = profile[b] * scale * strength;
+            let gain = if mag > 0.0 {
+                (1.0 - noise / mag).max(GAIN_FLOOR)
+            } else {
+                GAIN_FLOOR
+            };
+            gains[b] = GAIN_SMOOTHING * gains[b] + (1.0 - GAIN_SMOOTHING) * gain;
+            cleaned[b] = Complex::new(s.re * gains[b], s.im * gains[b]);
+        }
+        out.add_frame(k, &cleaned);
+    }
+    out.finish()
+}
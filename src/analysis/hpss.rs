@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use symphonia::core::dsp::complex::Complex;
+
+use super::stft::{Istft, Stft};
+use crate::audio::decode::MemoryAudio;
+
+const FFT_SIZE: usize = 2048;
+const HOP: usize = 512;
+/// Median filter lengths: across time (frames) for harmonics, across
+/// frequency (bins) for drums. Both ~200ms / ~370Hz at 44.1kHz.
+const TIME_KERNEL: usize = 17;
+const FREQ_KERNEL: usize = 17;
+
+/// Harmonic and percussive parts of a file; they sum back to the original.
+#[derive(Debug, Clone)]
+pub struct Stems {
+    pub harmonic: Arc<MemoryAudio>,
+    pub percussive: Arc<MemoryAudio>,
+}
+
+/// Median-filtering harmonic/percussive separation (Fitzgerald 2010) with
+/// soft masks: sustained partials are smooth along time, hits along frequency.
+pub fn separate(audio: &MemoryAudio) -> Stems {
+    let ch = audio.channels as usize;
+    let frames = audio.frames as usize;
+    let mut harmonic = vec![0.0f32; frames * ch];
+    let mut percussive = vec![0.0f32; frames * ch];
+
+    for c in 0..ch {
+        let signal: Vec<f32> = audio.data.iter().skip(c).step_by(ch).copied().collect();
+        let (h, p) = separate_channel(&signal);
+        for (f, (h, p)) in h.into_iter().zip(p).enumerate() {
+            harmonic[f * ch + c] = h;
+            percussive[f * ch + c] = p;
+        }
+    }
+
+    let stem = |data| {
+        Arc::new(MemoryAudio {
+            sample_rate: audio.sample_rate,
+            channels: audio.channels,
+            frames: audio.frames,
+            data,
+        })
+    };
+    Stems {
+        harmonic: stem(harmonic),
+        percussive: stem(percussive),
+    }
+}
+
+fn separate_channel(signal: &[f32]) -> (Vec<f32>, Vec<f32>) {
+    let mut stft = Stft::new(FFT_SIZE, HOP);
+    let bins = stft.bins();
+    let count = stft.frame_count(signal.len());
+    let half = TIME_KERNEL / 2;
+    let mut out_h = Istft::new(FFT_SIZE, HOP, signal.len());
+    let mut out_p = Istft::new(FFT_SIZE, HOP, signal.len());
+
+    // Sliding window of spectra centered on the frame being separated.
+    let mut window: VecDeque<(Vec<Complex>, Vec<f32>)> = VecDeque::with_capacity(TIME_KERNEL);
+    let mut column = Vec::with_capacity(TIME_KERNEL);
+    let mut row = Vec::with_capacity(FREQ_KERNEL);
+    let mut masked_h = vec![Complex::default(); bins];
+    let mut masked_p = vec![Complex::default(); bins];
+
+    // Frame index of `window[0]`.
+    let mut first = 0usize;
+    for k in 0..count + half {
+        if k < count {
+            let spectrum = stft.spectrum(signal, k).to_vec();
+            let mags = spectrum
+                .iter()
+                .map(|c| (c.re * c.re + c.im * c.im).sqrt())
+                .collect();
+            window.push_back((spectrum, mags));
+        }
+        let Some(center) = k.checked_sub(half) else {
+            continue;
+        };
+        while first + half < center {
+            window.pop_front();
+            first += 1;
+        }
+        let (spectrum, mags) = &window[center - first];
+
+        for b in 0..bins {
+            column.clear();
+            column.extend(window.iter().map(|(_, m)| m[b]));
+            let h = median(&mut column);
+
+            row.clear();
+            let lo = b.saturating_sub(FREQ_KERNEL / 2);
+            let hi = (b + FREQ_KERNEL / 2 + 1).min(bins);
+            row.extend_from_slice(&mags[lo..hi]);
+            let p = median(&mut row);
+
+            let (h2, p2) = (h * h, p * p);
+            let mask_h = if h2 + p2 > 0.0 { h2 / (h2 + p2) } else { 0.5 };
+            let s = spectrum[b];
+            masked_h[b] = Complex::new(s.re * mask_h, s.im * mask_h);
+            masked_p[b] = Complex::new(s.re * (1.0 - mask_h), s.im * (1.0 - mask_h));
+        }
+        out_h.add_frame(center, &masked_h);
+        out_p.add_frame(center, &masked_p);
+    }
+
+    (out_h.finish(), out_p.finish())
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    let mid = values.len() / 2;
+    *values.select_nth_unstable_by(mid, |a, b| a.total_cmp(b)).1
+}
@@ -0,0 +1,116 @@
+pub mod beats;
+pub mod chords;
+pub mod denoise;
+pub mod hpss;
+pub mod notes;
+pub mod onsets;
+pub mod pitch;
+pub mod spectrogram;
+pub mod stft;
+pub mod structure;
+pub mod transcribe;
+pub mod tuning;
+
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+
+use crate::audio::decode::MemoryAudio;
+use beats::TempoMap;
+use chords::ChordTimeline;
+use pitch::PitchTrack;
+use spectrogram::Spectrogram;
+use structure::Section;
+
+/// Events emitted by the background analysis job, one per finished analysis.
+#[derive(Debug)]
+pub enum AnalysisEvent {
+    Beats(TempoMap),
+    /// Onset times in seconds, ascending.
+    Onsets(Vec<f64>),
+    Chords(ChordTimeline),
+    /// Repeated passages, ordered by start time.
+    Sections(Vec<Section>),
+    Spectrogram(Arc<Spectrogram>),
+    Pitch(PitchTrack),
+    /// Tuning offset from A4 = 440 Hz, in cents.
+    Tuning(f32),
+}
+
+/// Spawn a background thread that analyses decoded PCM.
+/// The job stops after the current analysis once the receiver is dropped (e.g.
+/// another file was opened).
+pub fn spawn_analysis_job(audio: Arc<MemoryAudio>) -> mpsc::Receiver<AnalysisEvent> {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mono = stft::mono_mixdown(&audio);
+
+        // The spectrogram is on screen, so it goes first; the rest only
+        // annotates it.
+        let spec = spectrogram::compute(&mono, audio.sample_rate);
+        if tx.send(AnalysisEvent::Spectrogram(Arc::new(spec))).is_err() {
+            return;
+        }
+
+        let onset_env = onsets::onset_envelope(&mono, audio.sample_rate);
+        if tx
+            .send(AnalysisEvent::Onsets(onsets::pick_onsets(&onset_env)))
+            .is_err()
+        {
+            return;
+        }
+        let map = beats::estimate_tempo_map(&onset_env, 4);
+        if let Some(map) = &map
+            && tx.send(AnalysisEvent::Beats(map.clone())).is_err()
+        {
+            return;
+        }
+
+        let chroma = chords::beat_chroma(&mono, audio.sample_rate, map.as_ref());
+        if let Some(timeline) = chords::analyze(&chroma)
+            && tx.send(AnalysisEvent::Chords(timeline)).is_err()
+        {
+            return;
+        }
+        if tx
+            .send(AnalysisEvent::Sections(structure::find_sections(&chroma)))
+            .is_err()
+        {
+            return;
+        }
+
+        if let Some(cents) = tuning::estimate_cents(&mono, audio.sample_rate)
+            && tx.send(AnalysisEvent::Tuning(cents)).is_err()
+        {
+            return;
+        }
+
+        let _ = tx.send(AnalysisEvent::Pitch(pitch::track(&mono, audio.sample_rate)));
+    });
+
+    rx
+}
+
+/// Run harmonic/percussive separation in the background; the receiver gets a
+/// single `Stems` value when done.
+pub fn spawn_separation_job(audio: Arc<MemoryAudio>) -> mpsc::Receiver<hpss::Stems> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(hpss::separate(&audio));
+    });
+    rx
+}
+
+/// Run a denoise pass in the background; the receiver gets the cleaned audio.
+pub fn spawn_denoise_job(
+    audio: Arc<MemoryAudio>,
+    source: denoise::NoiseSource,
+    strength: f32,
+) -> mpsc::Receiver<Arc<MemoryAudio>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(denoise::denoise(&audio, source, strength));
+    });
+    rx
+}
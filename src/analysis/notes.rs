@@ -0,0 +1,27 @@
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B",
+];
+
+/// Reference pitch for MIDI note 69 (A4).
+pub const A4_HZ: f64 = 440.0;
+
+/// Frequency of a (fractional) MIDI note number.
+pub fn hz_from_midi(midi: f64) -> f64 {
+    A4_HZ * 2f64.powf((midi - 69.0) / 12.0)
+}
+
+/// Fractional MIDI note number of a frequency.
+pub fn midi_from_hz(hz: f64) -> f64 {
+    69.0 + 12.0 * (hz / A4_HZ).log2()
+}
+
+/// Pitch-class name without octave, e.g. "Bb".
+pub fn pitch_class_name(pc: i32) -> &'static str {
+    NOTE_NAMES[pc.rem_euclid(12) as usize]
+}
+
+/// Scientific pitch name for a MIDI note, e.g. 69 → "A4", 36 → "C2".
+pub fn note_name(midi: i32) -> String {
+    let octave = midi.div_euclid(12) - 1;
+    format!("{}{}", pitch_class_name(midi), octave)
+}
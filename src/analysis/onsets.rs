@@ -0,0 +1,103 @@
+use super::stft::Stft;
+
+/// FFT size for onset detection: short enough to keep attacks sharp.
+const FFT_SIZE: usize = 2048;
+/// Hop between novelty frames (~11.6ms at 44.1kHz).
+const HOP: usize = 512;
+/// Window for the local mean subtracted from the flux, in seconds.
+const MEAN_WINDOW_SECONDS: f64 = 0.5;
+/// Peak picking: an onset is the largest value within this many seconds either
+/// side, and no two onsets are closer than `MIN_ONSET_GAP_SECONDS`.
+const PEAK_WINDOW_SECONDS: f64 = 0.03;
+const MIN_ONSET_GAP_SECONDS: f64 = 0.05;
+
+/// Spectral-flux novelty curve: how much new energy appears in each frame.
+#[derive(Debug, Clone)]
+pub struct OnsetEnvelope {
+    pub sample_rate: u32,
+    pub hop: usize,
+    /// One non-negative value per STFT frame.
+    pub values: Vec<f32>,
+}
+
+impl OnsetEnvelope {
+    /// Frames per second.
+    pub fn frame_rate(&self) -> f64 {
+        self.sample_rate as f64 / self.hop as f64
+    }
+}
+
+/// Log-compressed spectral flux with the local mean removed.
+pub fn onset_envelope(mono: &[f32], sample_rate: u32) -> OnsetEnvelope {
+    let mut stft = Stft::new(FFT_SIZE, HOP);
+    let frames = stft.frame_count(mono.len());
+    let mut prev: Vec<f32> = vec![0.0; stft.bins()];
+    let mut mags = Vec::with_capacity(stft.bins());
+    let mut flux = Vec::with_capacity(frames);
+    for k in 0..frames {
+        stft.magnitudes(mono, k, &mut mags);
+        let mut sum = 0.0f32;
+        for (m, p) in mags.iter().zip(prev.iter_mut()) {
+            // Log compression keeps quiet instruments from being drowned out.
+            let c = (1.0 + 100.0 * m).ln();
+            sum += (c - *p).max(0.0);
+            *p = c;
+        }
+        flux.push(sum);
+    }
+
+    let half = ((MEAN_WINDOW_SECONDS * sample_rate as f64 / HOP as f64) / 2.0) as usize;
+    let values = subtract_local_mean(&flux, half.max(1));
+    OnsetEnvelope {
+        sample_rate,
+        hop: HOP,
+        values,
+    }
+}
+
+/// Onset times in seconds: local maxima of the envelope that stand out from
+/// their surroundings by more than a fraction of the overall level.
+pub fn pick_onsets(env: &OnsetEnvelope) -> Vec<f64> {
+    let v = &env.values;
+    if v.is_empty() {
+        return Vec::new();
+    }
+    let fr = env.frame_rate();
+    let w = ((PEAK_WINDOW_SECONDS * fr).round() as usize).max(1);
+    let gap = MIN_ONSET_GAP_SECONDS * fr;
+    let mean = v.iter().map(|x| *x as f64).sum::<f64>() / v.len() as f64;
+    let max = v.iter().fold(0.0f32, |m, x| m.max(*x));
+    let delta = ((mean * 0.5) as f32).max(max * 0.05);
+    let local = subtract_local_mean(v, w * 3);
+
+    let mut onsets: Vec<f64> = Vec::new();
+    let mut last = f64::NEG_INFINITY;
+    // Skip the edges: the first frame compares against silence and the window
+    // is incomplete there.
+    for i in w..v.len().saturating_sub(w) {
+        let (a, b) = (i - w, i + w + 1);
+        let is_peak = v[a..b].iter().all(|x| *x <= v[i]);
+        if is_peak && local[i] > delta && i as f64 - last >= gap {
+            onsets.push(i as f64 / fr);
+            last = i as f64;
+        }
+    }
+    onsets
+}
+
+/// `x[i] - mean(x[i-half..=i+half])`, half-wave rectified.
+fn subtract_local_mean(x: &[f32], half: usize) -> Vec<f32> {
+    let mut prefix = Vec::with_capacity(x.len() + 1);
+    prefix.push(0.0f64);
+    for v in x {
+        prefix.push(prefix.last().unwrap() + *v as f64);
+    }
+    (0..x.len())
+        .map(|i| {
+            let a = i.saturating_sub(half);
+            let b = (i + half + 1).min(x.len());
+            let mean = (prefix[b] - prefix[a]) / (b - a) as f64;
+            (x[i] - mean as f32).max(0.0)
+        })
+        .collect()
+}
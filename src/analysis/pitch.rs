@@ -0,0 +1,171 @@
+use symphonia::core::dsp::complex::Complex;
+use symphonia::core::dsp::fft::Fft;
+
+use super::notes::{midi_from_hz, note_name};
+
+/// Pitch frames per second.
+const FRAME_RATE: f64 = 100.0;
+/// Analysis window; its half sets the lowest detectable pitch (~45 Hz at 22 kHz).
+const WINDOW: usize = 1024;
+/// Highest fundamental considered; melody instruments and voice sit well below.
+const MAX_HZ: f64 = 1800.0;
+/// YIN threshold on the cumulative mean normalized difference.
+const YIN_THRESHOLD: f32 = 0.15;
+/// Frames quieter than this RMS are treated as unvoiced.
+const SILENCE_RMS: f32 = 0.01;
+/// Deviation from the nearest note, in cents, still reported as in tune.
+const IN_TUNE_CENTS: f32 = 10.0;
+
+/// Fundamental frequency over time for a monophonic line.
+#[derive(Debug, Clone)]
+pub struct PitchTrack {
+    pub frame_seconds: f64,
+    /// Fractional MIDI note per frame; `None` where no clear pitch was found.
+    pub frames: Vec<Option<f32>>,
+}
+
+impl PitchTrack {
+    /// Pitch at `t` seconds, if that frame is voiced.
+    pub fn at(&self, t: f64) -> Option<f32> {
+        if t < 0.0 {
+            return None;
+        }
+        let k = (t / self.frame_seconds).round() as usize;
+        self.frames.get(k).copied().flatten()
+    }
+}
+
+/// Describe a fractional MIDI note relative to the nearest note, e.g.
+/// "slightly flat Bb4 (-12 ¢)".
+pub fn describe_pitch(midi: f32) -> String {
+    let nearest = midi.round();
+    let cents = (midi - nearest) * 100.0;
+    let name = note_name(nearest as i32);
+    let qualifier = if cents.abs() <= IN_TUNE_CENTS {
+        ""
+    } else if cents.abs() <= 25.0 {
+        if cents < 0.0 {
+            "slightly flat "
+        } else {
+            "slightly sharp "
+        }
+    } else if cents < 0.0 {
+        "flat "
+    } else {
+        "sharp "
+    };
+    format!("{qualifier}{name} ({cents:+.0} ¢)")
+}
+
+/// Track the fundamental of `mono` with YIN. The signal is halved to ~22 kHz
+/// first when the rate allows, which keeps the FFT sizes small.
+pub fn track(mono: &[f32], sample_rate: u32) -> PitchTrack {
+    let (signal, sr) = if sample_rate > 32_000 {
+        let halved: Vec<f32> = mono
+            .chunks(2)
+            .map(|p| p.iter().sum::<f32>() / p.len() as f32)
+            .collect();
+        (halved, sample_rate as f64 / 2.0)
+    } else {
+        (mono.to_vec(), sample_rate as f64)
+    };
+
+    let hop = (sr / FRAME_RATE).round().max(1.0) as usize;
+    let frame_seconds = hop as f64 / sr;
+    let max_tau = WINDOW / 2;
+    let min_tau = ((sr / MAX_HZ).floor() as usize).max(2);
+
+    let fft = Fft::new(WINDOW * 2);
+    let mut buf = vec![Complex::default(); WINDOW * 2];
+    let mut frame = vec![0.0f32; WINDOW];
+    let mut diff = vec![0.0f32; max_tau + 1];
+    let mut cmnd = vec![1.0f32; max_tau + 1];
+    let mut prefix = vec![0.0f64; WINDOW + 1];
+
+    let count = signal.len().div_ceil(hop);
+    let frames = (0..count)
+        .map(|k| {
+            // Frame k is centered on sample k·hop; outside the signal is silence.
+            let start = (k * hop) as isize - (WINDOW / 2) as isize;
+            for (i, s) in frame.iter_mut().enumerate() {
+                let idx = start + i as isize;
+                *s = if idx >= 0 && (idx as usize) < signal.len() {
+                    signal[idx as usize]
+                } else {
+                    0.0
+                };
+            }
+            for (i, s) in frame.iter().enumerate() {
+                prefix[i + 1] = prefix[i] + (*s as f64) * (*s as f64);
+            }
+            let rms = (prefix[WINDOW] / WINDOW as f64).sqrt() as f32;
+            if rms < SILENCE_RMS {
+                return None;
+            }
+
+            // Autocorrelation through the FFT, zero-padded against wrap-around.
+            for (b, s) in buf
+                .iter_mut()
+                .zip(frame.iter().chain(std::iter::repeat(&0.0)))
+            {
+                *b = Complex::new(*s, 0.0);
+            }
+            fft.fft_inplace(&mut buf);
+            for b in buf.iter_mut() {
+                *b = Complex::new(b.re * b.re + b.im * b.im, 0.0);
+            }
+            fft.ifft_inplace(&mut buf);
+
+            // d(τ) = Σ (x[j] − x[j+τ])² over the overlapping part of the window.
+            for (tau, d) in diff.iter_mut().enumerate() {
+                let head = prefix[WINDOW - tau];
+                let tail = prefix[WINDOW] - prefix[tau];
+                *d = (head + tail - 2.0 * buf[tau].re as f64).max(0.0) as f32;
+            }
+            yin_pitch(&diff, &mut cmnd, min_tau).map(|tau| midi_from_hz(sr / tau) as f32)
+        })
+        .collect();
+
+    PitchTrack {
+        frame_seconds,
+        frames,
+    }
+}
+
+/// Period in (fractional) samples from a difference function, or `None` when
+/// no dip falls under the threshold.
+fn yin_pitch(diff: &[f32], cmnd: &mut [f32], min_tau: usize) -> Option<f64> {
+    let mut running = 0.0f32;
+    cmnd[0] = 1.0;
+    for tau in 1..diff.len() {
+        running += diff[tau];
+        cmnd[tau] = if running > 0.0 {
+            diff[tau] * tau as f32 / running
+        } else {
+            1.0
+        };
+    }
+
+    let mut tau = min_tau;
+    while tau + 1 < cmnd.len() {
+        if cmnd[tau] < YIN_THRESHOLD {
+            // Walk down to the bottom of this dip.
+            while tau + 1 < cmnd.len() && cmnd[tau + 1] < cmnd[tau] {
+                tau += 1;
+            }
+            if tau + 1 >= cmnd.len() {
+                return None;
+            }
+            let (y0, y1, y2) = (cmnd[tau - 1] as f64, cmnd[tau] as f64, cmnd[tau + 1] as f64);
+            let denom = y0 - 2.0 * y1 + y2;
+            let shift = if denom.abs() > f64::EPSILON {
+                (0.5 * (y0 - y2) / denom).clamp(-0.5, 0.5)
+            } else {
+                0.0
+            };
+            return Some(tau as f64 + shift);
+        }
+        tau += 1;
+    }
+    None
+}
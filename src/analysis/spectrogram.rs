@@ -0,0 +1,109 @@
+use super::notes::hz_from_midi;
+use super::stft::Stft;
+
+/// FFT size: ~90ms at 44.1kHz, enough to separate semitones from C2 upwards.
+const FFT_SIZE: usize = 4096;
+/// Hop between frames: ~12ms at 44.1kHz.
+const HOP: usize = 512;
+/// Lowest row of the log-frequency axis (MIDI note, C1).
+pub const LOW_MIDI: f64 = 24.0;
+/// Highest row of the log-frequency axis (MIDI note, C9).
+pub const HIGH_MIDI: f64 = 120.0;
+/// Log-frequency resolution.
+pub const ROWS_PER_SEMITONE: usize = 3;
+/// Magnitudes below this are drawn as black.
+const FLOOR_DB: f32 = -90.0;
+
+/// Log-frequency magnitude spectrogram, quantised to one byte per cell.
+#[derive(Debug)]
+pub struct Spectrogram {
+    pub sample_rate: u32,
+    pub hop: usize,
+    pub frames: usize,
+    pub rows: usize,
+    /// Row-major `[frame][row]`, row 0 = `LOW_MIDI`; 0..=255 maps `FLOOR_DB`..=0 dB.
+    pub data: Vec<u8>,
+}
+
+impl Spectrogram {
+    /// Seconds between frames.
+    pub fn frame_seconds(&self) -> f64 {
+        self.hop as f64 / self.sample_rate as f64
+    }
+
+    /// MIDI note at the center of `row`.
+    pub fn row_midi(row: usize) -> f64 {
+        LOW_MIDI + row as f64 / ROWS_PER_SEMITONE as f64
+    }
+
+    pub fn frame(&self, k: usize) -> &[u8] {
+        &self.data[k * self.rows..(k + 1) * self.rows]
+    }
+}
+
+/// Which FFT bins feed one log-frequency row.
+struct RowBand {
+    first: usize,
+    last: usize,
+    /// Fractional center bin; used when the band is narrower than one bin.
+    center: f64,
+    above_nyquist: bool,
+}
+
+/// Compute the spectrogram of a mono signal.
+pub fn compute(mono: &[f32], sample_rate: u32) -> Spectrogram {
+    let mut stft = Stft::new(FFT_SIZE, HOP);
+    let rows = ((HIGH_MIDI - LOW_MIDI) * ROWS_PER_SEMITONE as f64) as usize + 1;
+    let bins = stft.bins();
+    let bin_hz = stft.bin_hz(1.0, sample_rate);
+    let half_row = 0.5 / ROWS_PER_SEMITONE as f64;
+    let bands: Vec<RowBand> = (0..rows)
+        .map(|r| {
+            let midi = Spectrogram::row_midi(r);
+            let lo = hz_from_midi(midi - half_row) / bin_hz;
+            let hi = hz_from_midi(midi + half_row) / bin_hz;
+            let center = hz_from_midi(midi) / bin_hz;
+            RowBand {
+                first: lo.ceil() as usize,
+                last: (hi.floor() as usize).min(bins - 1),
+                center,
+                above_nyquist: center >= (bins - 1) as f64,
+            }
+        })
+        .collect();
+
+    let frames = stft.frame_count(mono.len());
+    let mut data = Vec::with_capacity(frames * rows);
+    let mut mags = Vec::with_capacity(bins);
+    for k in 0..frames {
+        stft.magnitudes(mono, k, &mut mags);
+        for band in &bands {
+            let mag = if band.above_nyquist {
+                0.0
+            } else if band.first <= band.last {
+                mags[band.first..=band.last]
+                    .iter()
+                    .fold(0.0f32, |acc, m| acc.max(*m))
+            } else {
+                let i0 = band.center.floor() as usize;
+                let frac = (band.center - i0 as f64) as f32;
+                mags[i0] + (mags[(i0 + 1).min(bins - 1)] - mags[i0]) * frac
+            };
+            data.push(quantize_db(mag));
+        }
+    }
+
+    Spectrogram {
+        sample_rate,
+        hop: HOP,
+        frames,
+        rows,
+        data,
+    }
+}
+
+fn quantize_db(mag: f32) -> u8 {
+    let db = 20.0 * mag.max(1e-9).log10();
+    let t = ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0);
+    (t * 255.0).round() as u8
+}
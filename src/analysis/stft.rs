@@ -0,0 +1,147 @@
+use symphonia::core::dsp::complex::Complex;
+use symphonia::core::dsp::fft::Fft;
+
+use crate::audio::decode::MemoryAudio;
+
+/// Hann-windowed short-time Fourier transform over a mono signal.
+/// Frame `k` is centered on sample `k * hop`, so frame times are `k * hop / sr`.
+pub struct Stft {
+    fft: Fft,
+    window: Vec<f32>,
+    buf: Vec<Complex>,
+    pub size: usize,
+    pub hop: usize,
+}
+
+impl Stft {
+    pub fn new(size: usize, hop: usize) -> Self {
+        let window = (0..size)
+            .map(|i| {
+                let phase = i as f32 / size as f32;
+                0.5 - 0.5 * (std::f32::consts::TAU * phase).cos()
+            })
+            .collect();
+        Self {
+            fft: Fft::new(size),
+            window,
+            buf: vec![Complex::default(); size],
+            size,
+            hop,
+        }
+    }
+
+    /// Number of bins in a magnitude spectrum (DC through Nyquist).
+    pub fn bins(&self) -> usize {
+        self.size / 2 + 1
+    }
+
+    /// Number of frames needed to cover `len` samples.
+    pub fn frame_count(&self, len: usize) -> usize {
+        len.div_ceil(self.hop)
+    }
+
+    /// Frequency in Hz of bin `bin`.
+    pub fn bin_hz(&self, bin: f64, sample_rate: u32) -> f64 {
+        bin * sample_rate as f64 / self.size as f64
+    }
+
+    /// Complex spectrum of frame `k`; samples outside the signal read as silence.
+    pub fn spectrum(&mut self, signal: &[f32], k: usize) -> &[Complex] {
+        let start = (k * self.hop) as isize - (self.size / 2) as isize;
+        for (i, c) in self.buf.iter_mut().enumerate() {
+            let idx = start + i as isize;
+            let s = if idx >= 0 && (idx as usize) < signal.len() {
+                signal[idx as usize]
+            } else {
+                0.0
+            };
+            *c = Complex::new(s * self.window[i], 0.0);
+        }
+        self.fft.fft_inplace(&mut self.buf);
+        &self.buf[..self.size / 2 + 1]
+    }
+
+    /// Magnitude spectrum of frame `k`, scaled so a full-scale sine peaks near 1.0.
+    pub fn magnitudes(&mut self, signal: &[f32], k: usize, out: &mut Vec<f32>) {
+        // Hann coherent gain is 0.5 and a real sine splits over ±f: N/4 overall.
+        let scale = 4.0 / self.size as f32;
+        let spectrum = self.spectrum(signal, k);
+        out.clear();
+        out.extend(
+            spectrum
+                .iter()
+                .map(|c| (c.re * c.re + c.im * c.im).sqrt() * scale),
+        );
+    }
+}
+
+/// Overlap-add resynthesis matching `Stft` framing: frame `k` is centered on
+/// sample `k * hop`, with a Hann synthesis window on top of the analysis one.
+pub struct Istft {
+    fft: Fft,
+    window: Vec<f32>,
+    buf: Vec<Complex>,
+    size: usize,
+    hop: usize,
+    out: Vec<f32>,
+    /// Sum of squared windows per sample, divided out at the end.
+    norm: Vec<f32>,
+}
+
+impl Istft {
+    pub fn new(size: usize, hop: usize, len: usize) -> Self {
+        let Stft {
+            fft, window, buf, ..
+        } = Stft::new(size, hop);
+        Self {
+            fft,
+            window,
+            buf,
+            size,
+            hop,
+            out: vec![0.0; len],
+            norm: vec![0.0; len],
+        }
+    }
+
+    /// Add frame `k` given its half spectrum (DC through Nyquist).
+    pub fn add_frame(&mut self, k: usize, half: &[Complex]) {
+        let n = self.size;
+        for (b, c) in half.iter().enumerate() {
+            self.buf[b] = *c;
+            if b > 0 && b < n / 2 {
+                self.buf[n - b] = Complex::new(c.re, -c.im);
+            }
+        }
+        self.fft.ifft_inplace(&mut self.buf);
+        let start = (k * self.hop) as isize - (n / 2) as isize;
+        for (i, (c, w)) in self.buf.iter().zip(&self.window).enumerate() {
+            let idx = start + i as isize;
+            if idx >= 0 && (idx as usize) < self.out.len() {
+                self.out[idx as usize] += c.re * w;
+                self.norm[idx as usize] += w * w;
+            }
+        }
+    }
+
+    pub fn finish(self) -> Vec<f32> {
+        self.out
+            .into_iter()
+            .zip(self.norm)
+            .map(|(s, n)| if n > 1e-6 { s / n } else { 0.0 })
+            .collect()
+    }
+}
+
+/// Channel-averaged copy of the PCM, the input most analyses work on.
+pub fn mono_mixdown(audio: &MemoryAudio) -> Vec<f32> {
+    let ch = audio.channels as usize;
+    if ch == 0 {
+        return Vec::new();
+    }
+    audio
+        .data
+        .chunks_exact(ch)
+        .map(|frame| frame.iter().sum::<f32>() / ch as f32)
+        .collect()
+}
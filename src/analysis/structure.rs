@@ -0,0 +1,321 @@
+use super::chords::ChromaSegment;
+
+/// Shortest repeat reported as a section.
+const MIN_SECTION_SECONDS: f64 = 6.0;
+/// Consecutive segments compared together, so that repeats of a progression
+/// line up rather than single matching chords.
+const EMBED: usize = 4;
+/// Fraction of off-diagonal similarities that count as a repeat.
+const SIMILARITY_QUANTILE: f64 = 0.9;
+/// Segments quieter than this fraction of the loudest never match.
+const SILENCE_RATIO: f32 = 0.05;
+/// Longer inputs are averaged into coarser segments, since the scan is
+/// quadratic in the segment count; this covers about eight minutes of beats
+/// at 120 bpm untouched.
+const MAX_SEGMENTS: usize = 1024;
+/// Resolution of the similarity histogram the threshold is read from.
+const HISTOGRAM_BINS: usize = 4096;
+
+/// One occurrence of a repeated passage.
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub start: f64,
+    pub end: f64,
+    /// Occurrences of the same passage share a group; 0 repeats most often.
+    pub group: usize,
+    /// e.g. "Chorus 2".
+    pub label: String,
+}
+
+/// Half-open range of segment indices.
+type Span = (usize, usize);
+
+/// Find repeated passages from the chroma self-similarity matrix: a repeat
+/// shows up as a stripe parallel to the main diagonal. Stripe ends cut the
+/// file into blocks, and blocks with matching content are grouped. The most
+/// repeated group is labelled "Chorus" and the next "Verse", which is a guess
+/// that holds for most pop forms.
+pub fn find_sections(segments: &[ChromaSegment]) -> Vec<Section> {
+    let coarse;
+    let segments = if segments.len() > MAX_SEGMENTS {
+        coarse = coarsen(segments, segments.len().div_ceil(MAX_SEGMENTS));
+        &coarse[..]
+    } else {
+        segments
+    };
+    let n = segments.len();
+    let Some(last) = segments.last() else {
+        return Vec::new();
+    };
+    let segment_seconds = (last.end - segments[0].start) / n as f64;
+    let min_len = ((MIN_SECTION_SECONDS / segment_seconds).ceil() as usize).max(2);
+    if n < min_len * 2 {
+        return Vec::new();
+    }
+
+    let loudest = segments
+        .iter()
+        .map(|s| s.chroma.iter().sum::<f32>())
+        .fold(0.0f32, f32::max);
+    let features: Vec<[f32; 12]> = segments
+        .iter()
+        .map(|s| {
+            let norm = s.chroma.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if norm <= 0.0 || s.chroma.iter().sum::<f32>() < loudest * SILENCE_RATIO {
+                [0.0; 12]
+            } else {
+                s.chroma.map(|v| v / norm)
+            }
+        })
+        .collect();
+    let similarity = |i: usize, j: usize| {
+        let count = EMBED.min(n - j);
+        (0..count)
+            .map(|e| {
+                let (a, b) = (&features[i + e], &features[j + e]);
+                a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>()
+            })
+            .sum::<f32>()
+            / count as f32
+    };
+
+    // The upper triangle is scanned one diagonal (lag) at a time, twice: first
+    // into a histogram for the threshold, then for stripes. Similarities of
+    // the unit-norm, non-negative features lie in [0, 1].
+    let diagonal = |lag: usize| (0..n - lag).map(move |i| similarity(i, i + lag));
+    let mut histogram = vec![0usize; HISTOGRAM_BINS];
+    for lag in min_len..n {
+        for v in diagonal(lag) {
+            let bin = (v.clamp(0.0, 1.0) * HISTOGRAM_BINS as f32) as usize;
+            histogram[bin.min(HISTOGRAM_BINS - 1)] += 1;
+        }
+    }
+    let total: usize = histogram.iter().sum();
+    let k = ((total as f64 * SIMILARITY_QUANTILE) as usize).min(total - 1);
+    let mut seen = 0;
+    let bin = histogram
+        .iter()
+        .position(|count| {
+            seen += count;
+            seen > k
+        })
+        .unwrap_or(0);
+    let threshold = bin as f32 / HISTOGRAM_BINS as f32;
+    if threshold <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut pairs: Vec<(Span, Span)> = Vec::new();
+    for lag in min_len..n {
+        let diag: Vec<f32> = diagonal(lag).collect();
+        // Light smoothing bridges single-beat dips without blurring the ends.
+        let smoothed = moving_average(&diag, EMBED / 2);
+        let mut i = 0;
+        while i < smoothed.len() {
+            if smoothed[i] < threshold {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < smoothed.len() && smoothed[i] >= threshold {
+                i += 1;
+            }
+            // Similarity at i covers EMBED segments, so the match runs on past
+            // the last value above threshold. A stripe longer than its lag is
+            // a passage repeating back to back.
+            let end = (i + EMBED - 1).min(start + lag).min(n - lag);
+            if end - start >= min_len {
+                pairs.push(((start, end), (start + lag, end + lag)));
+            }
+        }
+    }
+
+    // Where repeats start and end are the candidate section boundaries; cuts
+    // closer than half a section are one boundary.
+    let mut cuts: Vec<usize> = pairs
+        .iter()
+        .flat_map(|(a, b)| [a.0, a.1, b.0, b.1])
+        .chain([0, n])
+        .collect();
+    cuts.sort_unstable();
+    let mut bounds = vec![0];
+    let mut cluster: Vec<usize> = Vec::new();
+    for cut in cuts {
+        if cluster
+            .first()
+            .is_some_and(|first| cut - first > min_len / 2)
+        {
+            bounds.push(cluster[cluster.len() / 2]);
+            cluster.clear();
+        }
+        cluster.push(cut);
+    }
+    bounds.push(n);
+    // Slivers between two boundaries join the block before them.
+    let mut kept: Vec<usize> = vec![0];
+    for &b in &bounds[1..] {
+        if b - kept.last().unwrap() >= min_len / 2 {
+            kept.push(b);
+        } else if b == n {
+            *kept.last_mut().unwrap() = n;
+        }
+    }
+    let bounds = kept;
+    let blocks: Vec<Span> = bounds.windows(2).map(|w| (w[0], w[1])).collect();
+
+    // Blocks of similar length whose aligned content matches are one passage.
+    let mut parent: Vec<usize> = (0..blocks.len()).collect();
+    for i in 0..blocks.len() {
+        for j in i + 1..blocks.len() {
+            let (a, b) = (blocks[i], blocks[j]);
+            let (la, lb) = (a.1 - a.0, b.1 - b.0);
+            if (la.min(lb) as f64) < la.max(lb) as f64 * 0.75 {
+                continue;
+            }
+            if aligned_similarity(&similarity, a, b, n) >= threshold {
+                let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+                parent[rj] = ri;
+            }
+        }
+    }
+
+    // Back-to-back blocks of one passage (a riff played twice) form one occurrence.
+    let mut occurrences: Vec<(Span, usize)> = Vec::new();
+    for (k, block) in blocks.iter().enumerate() {
+        let root = find(&mut parent, k);
+        match occurrences.last_mut() {
+            Some((span, g)) if *g == root => span.1 = block.1,
+            _ => occurrences.push((*block, root)),
+        }
+    }
+    let mut group_of = vec![usize::MAX; blocks.len()];
+    let mut groups = 0;
+    for (_, root) in &occurrences {
+        let repeats = occurrences.iter().filter(|(_, r)| r == root).count() > 1;
+        if repeats && group_of[*root] == usize::MAX {
+            group_of[*root] = groups;
+            groups += 1;
+        }
+    }
+    let accepted: Vec<(Span, usize)> = occurrences
+        .into_iter()
+        .filter(|(_, root)| group_of[*root] != usize::MAX)
+        .map(|(span, root)| (span, group_of[root]))
+        .collect();
+
+    // Rank groups by occurrence count, then loudness.
+    let loudness = |span: Span| {
+        segments[span.0..span.1]
+            .iter()
+            .map(|s| s.chroma.iter().sum::<f32>())
+            .sum::<f32>()
+            / (span.1 - span.0) as f32
+    };
+    let mut ranking: Vec<(usize, usize, f32)> = (0..groups)
+        .map(|g| {
+            let spans: Vec<Span> = accepted
+                .iter()
+                .filter(|(_, group)| *group == g)
+                .map(|(span, _)| *span)
+                .collect();
+            let level = spans.iter().map(|s| loudness(*s)).sum::<f32>() / spans.len() as f32;
+            (g, spans.len(), level)
+        })
+        .collect();
+    ranking.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.total_cmp(&a.2)));
+    let mut rank = vec![0; groups];
+    for (r, (g, _, _)) in ranking.iter().enumerate() {
+        rank[*g] = r;
+    }
+
+    let mut counts = vec![0; groups];
+    accepted
+        .iter()
+        .map(|(span, g)| {
+            let group = rank[*g];
+            counts[group] += 1;
+            Section {
+                start: segments[span.0].start,
+                end: segments[span.1 - 1].end,
+                group,
+                label: format!("{} {}", group_name(group), counts[group]),
+            }
+        })
+        .collect()
+}
+
+/// Average every `factor` consecutive segments into one.
+fn coarsen(segments: &[ChromaSegment], factor: usize) -> Vec<ChromaSegment> {
+    segments
+        .chunks(factor)
+        .map(|chunk| {
+            let mut chroma = [0.0f32; 12];
+            for s in chunk {
+                for (sum, v) in chroma.iter_mut().zip(&s.chroma) {
+                    *sum += v / chunk.len() as f32;
+                }
+            }
+            ChromaSegment {
+                start: chunk[0].start,
+                end: chunk[chunk.len() - 1].end,
+                chroma,
+            }
+        })
+        .collect()
+}
+
+/// Group name by rank.
+fn group_name(rank: usize) -> String {
+    match rank {
+        0 => "Chorus".to_string(),
+        1 => "Verse".to_string(),
+        r => format!("Part {}", (b'A' + (r % 26) as u8) as char),
+    }
+}
+
+/// Mean similarity of two blocks compared segment by segment, allowing a
+/// small offset for boundaries that landed a beat or two apart.
+fn aligned_similarity(
+    similarity: &impl Fn(usize, usize) -> f32,
+    a: Span,
+    b: Span,
+    n: usize,
+) -> f32 {
+    let len = (a.1 - a.0).min(b.1 - b.0);
+    (-2isize..=2)
+        .filter_map(|shift| {
+            let start = b.0.checked_add_signed(shift)?;
+            (start + len <= n).then(|| {
+                (0..len)
+                    .map(|k| similarity(a.0 + k, start + k))
+                    .sum::<f32>()
+                    / len as f32
+            })
+        })
+        .fold(0.0, f32::max)
+}
+
+/// Union-find root with path halving.
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Centered moving average with `half` values on each side.
+fn moving_average(values: &[f32], half: usize) -> Vec<f32> {
+    let mut prefix = Vec::with_capacity(values.len() + 1);
+    prefix.push(0.0f64);
+    for v in values {
+        prefix.push(prefix.last().unwrap() + *v as f64);
+    }
+    (0..values.len())
+        .map(|i| {
+            let a = i.saturating_sub(half);
+            let b = (i + half + 1).min(values.len());
+            ((prefix[b] - prefix[a]) / (b - a) as f64) as f32
+        })
+        .collect()
+}
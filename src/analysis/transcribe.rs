@@ -0,0 +1,96 @@
+use super::pitch::PitchTrack;
+use crate::audio::decode::MemoryAudio;
+
+/// Notes shorter than this are dropped as glitches.
+const MIN_NOTE_SECONDS: f64 = 0.06;
+/// Median filter length, in pitch frames, applied before rounding to notes.
+const SMOOTH_FRAMES: usize = 5;
+
+/// One transcribed note.
+#[derive(Debug, Clone, Copy)]
+pub struct NoteEvent {
+    pub start: f64,
+    pub end: f64,
+    pub midi: u8,
+    pub velocity: u8,
+}
+
+/// Turn the pitch track within `[t0, t1]` into notes: a note lasts while the
+/// smoothed pitch stays on one semitone, and a detected onset always starts a
+/// new one so repeated notes are not merged. Velocity follows the peak level.
+pub fn transcribe(
+    track: &PitchTrack,
+    onsets: &[f64],
+    audio: &MemoryAudio,
+    t0: f64,
+    t1: f64,
+) -> Vec<NoteEvent> {
+    let dt = track.frame_seconds;
+    let k0 = ((t0 / dt).floor().max(0.0) as usize).min(track.frames.len());
+    let k1 = ((t1 / dt).ceil().max(0.0) as usize).min(track.frames.len());
+    let smoothed = median_pitch(&track.frames, SMOOTH_FRAMES / 2);
+
+    let mut notes = Vec::new();
+    let mut current: Option<(i32, f64)> = None;
+    let mut next_onset = onsets.partition_point(|o| *o < t0);
+    let mut close = |current: &mut Option<(i32, f64)>, end: f64| {
+        if let Some((midi, start)) = current.take()
+            && end - start >= MIN_NOTE_SECONDS
+            && (0..=127).contains(&midi)
+        {
+            notes.push(NoteEvent {
+                start,
+                end,
+                midi: midi as u8,
+                velocity: velocity(audio, start, end),
+            });
+        }
+    };
+    for (k, pitch) in smoothed.iter().enumerate().take(k1).skip(k0) {
+        let t = (k as f64 * dt).max(t0);
+        let mut onset_here = false;
+        while onsets.get(next_onset).is_some_and(|o| *o <= t) {
+            onset_here = true;
+            next_onset += 1;
+        }
+        let midi = pitch.map(|m| m.round() as i32);
+        match (current, midi) {
+            (Some((cur, _)), Some(m)) if cur == m && !onset_here => {}
+            _ => {
+                close(&mut current, t);
+                current = midi.map(|m| (m, t));
+            }
+        }
+    }
+    close(&mut current, t1.min(k1 as f64 * dt));
+    notes
+}
+
+/// Median over voiced neighbours within `half` frames; unvoiced frames stay unvoiced.
+fn median_pitch(frames: &[Option<f32>], half: usize) -> Vec<Option<f32>> {
+    let mut window: Vec<f32> = Vec::with_capacity(half * 2 + 1);
+    (0..frames.len())
+        .map(|k| {
+            frames[k]?;
+            window.clear();
+            let a = k.saturating_sub(half);
+            let b = (k + half + 1).min(frames.len());
+            window.extend(frames[a..b].iter().flatten());
+            window.sort_by(|x, y| x.total_cmp(y));
+            Some(window[window.len() / 2])
+        })
+        .collect()
+}
+
+/// MIDI velocity from the peak sample level between `start` and `end`.
+fn velocity(audio: &MemoryAudio, start: f64, end: f64) -> u8 {
+    let sr = audio.sample_rate as f64;
+    let ch = audio.channels as usize;
+    let f0 = ((start * sr) as usize).min(audio.frames as usize);
+    let f1 = ((end * sr) as usize).clamp(f0, audio.frames as usize);
+    let peak = audio.data[f0 * ch..f1 * ch]
+        .iter()
+        .fold(0.0f32, |m, s| m.max(s.abs()));
+    // Square root spreads typical (quiet-ish) levels over the useful range.
+    (peak.min(1.0).sqrt() * 127.0).round().clamp(1.0, 127.0) as u8
+}
@@ -0,0 +1,64 @@
+use super::notes::midi_from_hz;
+use super::stft::Stft;
+
+/// Long frames resolve low partials finely enough for cent-level estimates.
+const FFT_SIZE: usize = 8192;
+const HOP: usize = 4096;
+/// Partials considered; the fundamentals of most instruments and their first
+/// harmonics, above rumble and below noisy cymbal content.
+const MIN_HZ: f64 = 80.0;
+const MAX_HZ: f64 = 5000.0;
+/// Peaks weaker than this fraction of the frame's strongest are ignored.
+const PEAK_RATIO: f32 = 0.1;
+/// Below this agreement (resultant length of the offsets) the estimate is noise.
+const MIN_AGREEMENT: f64 = 0.1;
+
+/// Offset of the recording's tuning from A4 = 440 Hz, in cents (−50..50):
+/// the magnitude-weighted circular mean of how far spectral peaks sit from
+/// the nearest equal-tempered semitone. `None` without clear tonal content.
+pub fn estimate_cents(mono: &[f32], sample_rate: u32) -> Option<f32> {
+    let mut stft = Stft::new(FFT_SIZE, HOP);
+    let lo = (MIN_HZ * FFT_SIZE as f64 / sample_rate as f64)
+        .ceil()
+        .max(1.0) as usize;
+    let hi = ((MAX_HZ * FFT_SIZE as f64 / sample_rate as f64) as usize).min(stft.bins() - 2);
+    if lo >= hi {
+        return None;
+    }
+
+    let (mut sum_sin, mut sum_cos, mut total) = (0.0f64, 0.0f64, 0.0f64);
+    let mut mags = Vec::with_capacity(stft.bins());
+    for k in 0..stft.frame_count(mono.len()) {
+        stft.magnitudes(mono, k, &mut mags);
+        let strongest = mags[lo..=hi].iter().fold(0.0f32, |m, v| m.max(*v));
+        if strongest <= 0.0 {
+            continue;
+        }
+        for b in lo..=hi {
+            let m = mags[b];
+            if m < strongest * PEAK_RATIO || m <= mags[b - 1] || m < mags[b + 1] {
+                continue;
+            }
+            // Parabolic interpolation on log magnitude for the true peak frequency.
+            let (a, c) = (mags[b - 1].max(1e-12).ln(), mags[b + 1].max(1e-12).ln());
+            let mid = m.ln();
+            let denom = a - 2.0 * mid + c;
+            let shift = if denom.abs() > f32::EPSILON {
+                (0.5 * (a - c) / denom).clamp(-0.5, 0.5)
+            } else {
+                0.0
+            };
+            let midi = midi_from_hz(stft.bin_hz(b as f64 + shift as f64, sample_rate));
+            let angle = std::f64::consts::TAU * (midi - midi.round());
+            let w = m as f64;
+            sum_sin += w * angle.sin();
+            sum_cos += w * angle.cos();
+            total += w;
+        }
+    }
+
+    if total <= 0.0 || sum_sin.hypot(sum_cos) / total < MIN_AGREEMENT {
+        return None;
+    }
+    Some((sum_sin.atan2(sum_cos) / std::f64::consts::TAU * 100.0) as f32)
+}
@@ -1,11 +1,42 @@
 use eframe::egui;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
+use crate::analysis::beats::TempoMap;
+use crate::analysis::chords::ChordTimeline;
+use crate::analysis::denoise::NoiseSource;
+use crate::analysis::hpss::Stems;
+use crate::analysis::pitch::{PitchTrack, describe_pitch};
+use crate::analysis::spectrogram::Spectrogram;
+use crate::analysis::structure::Section;
+use crate::analysis::transcribe::transcribe;
+use crate::analysis::{AnalysisEvent, spawn_analysis_job, spawn_denoise_job, spawn_separation_job};
 use crate::audio::decode::{DecodedInfo, LoadEvent, MemoryAudio, spawn_decode_job};
-use crate::audio::playback::Player;
-use crate::ui::waveform::{WaveformResult, draw_waveform};
+use crate::audio::playback::{Player, StemMix};
+use crate::formats::markers::{self, Marker, MarkerFormat, format_time};
+use crate::formats::midi;
+use crate::formats::project::{self, AudioRef, Playback, Project};
+use crate::library::{CUE_SLOTS, LoopLibrary};
+use crate::session::{FileState, Prefs, Session, WindowGeometry};
+use crate::ui::chords::{ChordLaneView, draw_chord_lane};
+use crate::ui::loops::{LoopAction, LoopLanesView, draw_loop_lanes, draw_loop_sidebar};
+use crate::ui::overview::draw_overview;
+use crate::ui::spectrogram::{SpectrogramCache, SpectrogramView, draw_spectrogram};
+use crate::ui::waveform::{
+    WaveformCache, WaveformLanes, WaveformResult, WaveformView, draw_waveform, section_color,
+};
+
+/// Narrowest zoom, in frames: still enough samples to read the waveform shape.
+const MIN_VIEW_FRAMES: f64 = 32.0;
+/// Zoom step for +/- keys and buttons.
+const ZOOM_STEP: f64 = 1.5;
+/// How far a snapped marker may move to reach a zero crossing.
+const ZERO_CROSSING_SECONDS: f64 = 0.005;
+/// Changes are written to the session file at most this often.
+const SESSION_SAVE_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Clone, Copy, Debug)]
 struct LoopRange {
@@ -13,10 +44,84 @@ struct LoopRange {
     end: f64,
 }
 
+/// How the waveform view tracks the playhead during playback.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FollowMode {
+    Off,
+    /// Jump a page once the playhead leaves the view.
+    Page,
+    /// Keep the playhead centered.
+    Continuous,
+}
+
+impl FollowMode {
+    fn label(self) -> &'static str {
+        match self {
+            FollowMode::Off => "Off",
+            FollowMode::Page => "Page",
+            FollowMode::Continuous => "Continuous",
+        }
+    }
+}
+
+/// Which signal the player plays once stems are separated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PlaybackSource {
+    Original,
+    Harmonic,
+    Percussive,
+    /// Both stems, weighted by `stem_balance`.
+    Blend,
+}
+
+impl PlaybackSource {
+    fn label(self) -> &'static str {
+        match self {
+            PlaybackSource::Original => "Original",
+            PlaybackSource::Harmonic => "Harmonic",
+            PlaybackSource::Percussive => "Percussive",
+            PlaybackSource::Blend => "Blend",
+        }
+    }
+}
+
+/// What loop markers snap to while dragging.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapMode {
+    Off,
+    Beat,
+    Bar,
+    /// Detected note attacks.
+    Onset,
+}
+
+impl SnapMode {
+    fn label(self) -> &'static str {
+        match self {
+            SnapMode::Off => "Off",
+            SnapMode::Beat => "Beat",
+            SnapMode::Bar => "Bar",
+            SnapMode::Onset => "Onset",
+        }
+    }
+}
+
+/// What the onset navigation keys and buttons move.
+#[derive(Clone, Copy, Debug)]
+enum OnsetTarget {
+    Playhead,
+    Start,
+    End,
+}
+
 #[derive(Clone, Copy, Debug)]
 enum MarkerHandle {
     Start,
     End,
+    Playhead,
+    /// A beat of the tempo map; dragging pins it as a warp marker. Bar 1 is
+    /// always grabbable, other beats while Alt is held.
+    Beat(i64),
 }
 
 impl LoopRange {
@@ -45,25 +150,85 @@ impl LoopRange {
 pub struct LoopahApp {
     selected_file: Option<PathBuf>,
     info: Option<DecodedInfo>,
-    mem_audio: Option<MemoryAudio>,
+    mem_audio: Option<Arc<MemoryAudio>>,
     player: Option<Player>,
     load_events: Option<mpsc::Receiver<LoadEvent>>,
     stream_rx: Option<mpsc::Receiver<Arc<Vec<f32>>>>,
     meta_sample_rate: Option<u32>,
     meta_channels: Option<u16>,
     load_error: Option<String>,
+    analysis_events: Option<mpsc::Receiver<AnalysisEvent>>,
+    spectrogram: Option<Arc<Spectrogram>>,
+    beat_grid: Option<TempoMap>,
+    show_beat_grid: bool,
+    /// Click along with the tempo map during playback.
+    metronome: bool,
+    /// First and last bar for "loop bars", 1-based and inclusive.
+    loop_bars: (i64, i64),
+    /// Detected onset times in seconds, ascending.
+    onsets: Vec<f64>,
+    chords: Option<ChordTimeline>,
+    show_chords: bool,
+    /// Repeated passages, offered as loop suggestions.
+    sections: Vec<Section>,
+    show_sections: bool,
+    pitch_track: Option<PitchTrack>,
+    show_pitch: bool,
+    /// Playback transposition; chord and key labels follow it.
+    pitch_semitones: f32,
+    /// Playback tempo factor, pitch preserved.
+    speed: f32,
+    volume: f32,
+    library: LoopLibrary,
+    /// Detected tuning offset of the recording, in cents.
+    tuning_cents: Option<f32>,
+    /// The spectrum at the playhead is held as a drone.
+    frozen: bool,
+    separation_events: Option<mpsc::Receiver<Stems>>,
+    denoise_events: Option<mpsc::Receiver<Arc<MemoryAudio>>>,
+    denoised: Option<Arc<MemoryAudio>>,
+    /// Play `denoised` instead of the decoded audio.
+    use_denoised: bool,
+    denoise_strength: f32,
+    stems: Option<Stems>,
+    /// `stems` were separated from the denoised audio.
+    stems_denoised: bool,
+    source: PlaybackSource,
+    /// 0 = harmonic only, 1 = percussive only, 0.5 = both at full level.
+    stem_balance: f32,
+    snap_mode: SnapMode,
+    /// After snapping, move markers onto the nearest zero crossing.
+    snap_zero_crossings: bool,
+    /// Recent tap-tempo presses (egui input time, seconds).
+    tap_times: Vec<f64>,
     loop_range: Option<LoopRange>,
     loop_drag_anchor: Option<f64>,
     marker_drag: Option<MarkerHandle>,
+    /// Play short grains under the pointer while dragging the playhead.
+    scrub_audio: bool,
 
     // Waveform view state (seconds):
     view_x_min: f64,
     view_x_max: f64,
+    waveform_lanes: WaveformLanes,
+    waveform_cache: WaveformCache,
+    show_spectrogram: bool,
+    spectrogram_cache: SpectrogramCache,
+    follow_mode: FollowMode,
+    /// Set by a manual pan; cleared on the next Play.
+    follow_paused: bool,
+
+    /// Project being opened; applied once its audio has decoded.
+    pending_project: Option<Project>,
+    session: Session,
+    /// The session as last written, to skip unchanged saves.
+    session_json: String,
+    session_saved_at: Instant,
 }
 
 impl LoopahApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Self {
+    pub fn new(_cc: &eframe::CreationContext<'_>, session: Session) -> Self {
+        let mut app = Self {
             selected_file: None,
             info: None,
             mem_audio: None,
@@ -73,16 +238,174 @@ impl LoopahApp {
             meta_sample_rate: None,
             meta_channels: None,
             load_error: None,
+            analysis_events: None,
+            spectrogram: None,
+            beat_grid: None,
+            show_beat_grid: true,
+            metronome: false,
+            loop_bars: (1, 4),
+            onsets: Vec::new(),
+            chords: None,
+            show_chords: true,
+            sections: Vec::new(),
+            show_sections: true,
+            pitch_track: None,
+            show_pitch: true,
+            pitch_semitones: 0.0,
+            speed: 1.0,
+            volume: 1.0,
+            library: LoopLibrary::default(),
+            tuning_cents: None,
+            frozen: false,
+            separation_events: None,
+            denoise_events: None,
+            denoised: None,
+            use_denoised: true,
+            denoise_strength: 1.5,
+            stems: None,
+            stems_denoised: false,
+            source: PlaybackSource::Original,
+            stem_balance: 0.5,
+            snap_mode: SnapMode::Off,
+            snap_zero_crossings: false,
+            tap_times: Vec::new(),
             loop_range: None,
             loop_drag_anchor: None,
             marker_drag: None,
+            scrub_audio: true,
             view_x_min: 0.0,
             view_x_max: 10.0, // temporary; reset on file open
+            waveform_lanes: WaveformLanes::default(),
+            waveform_cache: WaveformCache::default(),
+            show_spectrogram: true,
+            spectrogram_cache: SpectrogramCache::default(),
+            follow_mode: FollowMode::Page,
+            follow_paused: false,
+            pending_project: None,
+            session_json: serde_json::to_string(&session).unwrap_or_default(),
+            session,
+            session_saved_at: Instant::now(),
+        };
+        app.apply_prefs(app.session.prefs.clone());
+        if let Some(path) = app.session.last_file.clone().filter(|p| p.is_file()) {
+            app.open_file(path);
+        }
+        app
+    }
+
+    fn apply_prefs(&mut self, prefs: Prefs) {
+        self.show_beat_grid = prefs.show_beat_grid;
+        self.show_chords = prefs.show_chords;
+        self.show_sections = prefs.show_sections;
+        self.show_pitch = prefs.show_pitch;
+        self.show_spectrogram = prefs.show_spectrogram;
+        self.metronome = prefs.metronome;
+        self.scrub_audio = prefs.scrub_audio;
+        self.follow_mode = prefs.follow_mode;
+        self.snap_mode = prefs.snap_mode;
+        self.snap_zero_crossings = prefs.snap_zero_crossings;
+        self.waveform_lanes = prefs.waveform_lanes;
+        self.denoise_strength = prefs.denoise_strength;
+    }
+
+    fn prefs(&self) -> Prefs {
+        Prefs {
+            show_beat_grid: self.show_beat_grid,
+            show_chords: self.show_chords,
+            show_sections: self.show_sections,
+            show_pitch: self.show_pitch,
+            show_spectrogram: self.show_spectrogram,
+            metronome: self.metronome,
+            scrub_audio: self.scrub_audio,
+            follow_mode: self.follow_mode,
+            snap_mode: self.snap_mode,
+            snap_zero_crossings: self.snap_zero_crossings,
+            waveform_lanes: self.waveform_lanes,
+            denoise_strength: self.denoise_strength,
+        }
+    }
+
+    /// Start loading `path`, keeping the current file's state in the session.
+    fn open_file(&mut self, path: PathBuf) {
+        self.remember_file();
+        self.reset_state();
+        self.selected_file = Some(path.clone());
+        self.session.last_file = Some(path.clone());
+        let (events, stream_rx) = spawn_decode_job(path);
+        self.load_events = Some(events);
+        self.stream_rx = Some(stream_rx);
+    }
+
+    /// Copy the open file's loops, view and playback settings into the session.
+    fn remember_file(&mut self) {
+        let (Some(path), Some(info)) = (&self.selected_file, &self.info) else {
+            return;
+        };
+        self.session.remember_file(FileState {
+            path: path.clone(),
+            fingerprint: info.fingerprint,
+            library: self.library.clone(),
+            loop_range: self.loop_range.map(|r| (r.start, r.end)),
+            view: (self.view_x_min, self.view_x_max),
+            speed: self.speed,
+            pitch: self.pitch_semitones,
+            volume: self.volume,
+        });
+    }
+
+    /// What was saved for the file just decoded, if anything.
+    fn saved_file_state(&self) -> Option<FileState> {
+        let (Some(path), Some(info)) = (&self.selected_file, &self.info) else {
+            return None;
+        };
+        self.session.file(path, info.fingerprint).cloned()
+    }
+
+    /// Write the session if it changed, at most every `SESSION_SAVE_INTERVAL`
+    /// unless `force`.
+    fn save_session(&mut self, force: bool) {
+        if !force && self.session_saved_at.elapsed() < SESSION_SAVE_INTERVAL {
+            return;
+        }
+        self.session_saved_at = Instant::now();
+        self.remember_file();
+        self.session.prefs = self.prefs();
+        let json = match serde_json::to_string_pretty(&self.session) {
+            Ok(json) => json,
+            Err(e) => {
+                eprintln!("Session save failed: {e}");
+                return;
+            }
+        };
+        if json == self.session_json {
+            return;
+        }
+        match Session::store(&json) {
+            Ok(()) => self.session_json = json,
+            Err(e) => eprintln!("Session save failed: {e:#}"),
+        }
+    }
+
+    /// Track the window's placement so it reopens the same way.
+    fn capture_window(&mut self, ctx: &egui::Context) {
+        let geometry = ctx.input(|i| {
+            let vp = i.viewport();
+            if vp.minimized == Some(true) || vp.fullscreen == Some(true) {
+                return None;
+            }
+            vp.inner_rect.map(|inner| WindowGeometry {
+                position: vp.outer_rect.map(|outer| [outer.min.x, outer.min.y]),
+                size: [inner.width(), inner.height()],
+            })
+        });
+        if geometry.is_some() {
+            self.session.window = geometry;
         }
     }
 
     fn reset_state(&mut self) {
         self.info = None;
+        self.pending_project = None;
         self.mem_audio = None;
         self.player = None;
         self.load_events = None;
@@ -90,11 +413,29 @@ impl LoopahApp {
         self.meta_sample_rate = None;
         self.meta_channels = None;
         self.load_error = None;
+        self.analysis_events = None;
+        self.spectrogram = None;
+        self.beat_grid = None;
+        self.onsets.clear();
+        self.chords = None;
+        self.sections.clear();
+        self.pitch_track = None;
+        self.tuning_cents = None;
+        self.library = LoopLibrary::default();
+        self.frozen = false;
+        self.separation_events = None;
+        self.denoise_events = None;
+        self.denoised = None;
+        self.stems = None;
+        self.stems_denoised = false;
+        self.tap_times.clear();
         self.loop_range = None;
         self.loop_drag_anchor = None;
         self.marker_drag = None;
         self.view_x_min = 0.0;
         self.view_x_max = 10.0;
+        self.waveform_cache = WaveformCache::default();
+        self.spectrogram_cache = SpectrogramCache::default();
     }
 
     fn poll_loader(&mut self) {
@@ -118,15 +459,44 @@ impl LoopahApp {
                         }
                     }
                     LoadEvent::PreviewReady { info, audio } => {
-                        self.view_x_min = 0.0;
-                        self.view_x_max =
-                            (info.total_frames as f64 / info.sample_rate as f64).max(1.0);
+                        // Start on the audible part; leading silence is common in memos.
+                        let (audible_start, audible_end) = info.audible_range();
+                        let duration = file_duration_seconds(&info);
+                        self.view_x_min = audible_start;
+                        self.view_x_max = audible_end.max(audible_start + 1.0);
+                        let audio = Arc::new(audio);
                         self.mem_audio = Some(audio.clone());
+                        self.analysis_events = Some(spawn_analysis_job(audio.clone()));
                         self.info = Some(info);
-                        let duration = file_duration_seconds(self.info.as_ref().unwrap());
-                        self.loop_range = Some(LoopRange::ordered(0.0, duration));
+                        self.loop_range = Some(LoopRange::ordered(audible_start, audible_end));
                         self.loop_drag_anchor = None;
                         self.marker_drag = None;
+                        // Bring back loops, view and playback settings from last time.
+                        if let Some(state) = self.saved_file_state() {
+                            self.library = state.library;
+                            self.loop_range = state
+                                .loop_range
+                                .map(|(a, b)| LoopRange::ordered(a, b).clamp(duration));
+                            let (x_min, x_max) = state.view;
+                            if x_max > x_min {
+                                self.view_x_min = x_min;
+                                self.view_x_max = x_max;
+                            }
+                            self.speed = state.speed;
+                            self.pitch_semitones = state.pitch;
+                            self.volume = state.volume;
+                        }
+                        if let Some(project) = self.pending_project.take() {
+                            self.library = project.library;
+                            self.loop_range = project
+                                .loop_range
+                                .map(|(a, b)| LoopRange::ordered(a, b).clamp(duration));
+                            self.beat_grid = project.tempo_map;
+                            self.speed = project.playback.speed;
+                            self.pitch_semitones = project.playback.pitch;
+                            self.volume = project.playback.volume;
+                            self.metronome = project.playback.metronome;
+                        }
                         let prev_playing = self
                             .player
                             .as_ref()
@@ -140,7 +510,15 @@ impl LoopahApp {
                         match Player::from_memory(audio) {
                             Ok(p) => {
                                 self.player = Some(p);
+                                self.frozen = false;
                                 self.sync_player_loop();
+                                self.sync_metronome();
+                                if let Some(player) = &self.player {
+                                    player.set_pitch_semitones(self.pitch_semitones);
+                                    player.set_speed(self.speed as f64);
+                                    player.set_volume(self.volume);
+                                }
+                                self.sync_stem_mix();
                                 if let Some(player) = &self.player {
                                     player.set_position_seconds(prev_pos);
                                     if !prev_playing {
@@ -166,12 +544,83 @@ impl LoopahApp {
             self.load_events = None;
         }
     }
+
+    fn poll_separation(&mut self) {
+        let Some(rx) = &self.separation_events else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok(stems) => {
+                self.stems = Some(stems);
+                self.separation_events = None;
+                self.sync_stem_mix();
+            }
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => self.separation_events = None,
+        }
+    }
+
+    fn poll_denoise(&mut self) {
+        let Some(rx) = &self.denoise_events else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok(audio) => {
+                self.denoised = Some(audio);
+                self.use_denoised = true;
+                self.denoise_events = None;
+                self.sync_source();
+            }
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => self.denoise_events = None,
+        }
+    }
+
+    fn poll_analysis(&mut self) {
+        let Some(rx) = &self.analysis_events else {
+            return;
+        };
+        loop {
+            match rx.try_recv() {
+                // A map from a project or tap tempo wins over detection.
+                Ok(AnalysisEvent::Beats(map)) if self.beat_grid.is_none() => {
+                    self.beat_grid = Some(map);
+                    self.sync_metronome();
+                }
+                Ok(AnalysisEvent::Beats(_)) => {}
+                Ok(AnalysisEvent::Onsets(onsets)) => self.onsets = onsets,
+                Ok(AnalysisEvent::Chords(chords)) => self.chords = Some(chords),
+                Ok(AnalysisEvent::Tuning(cents)) => self.tuning_cents = Some(cents),
+                Ok(AnalysisEvent::Sections(sections)) => self.sections = sections,
+                Ok(AnalysisEvent::Spectrogram(spec)) => self.spectrogram = Some(spec),
+                Ok(AnalysisEvent::Pitch(track)) => self.pitch_track = Some(track),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.analysis_events = None;
+                    break;
+                }
+            }
+        }
+    }
 }
 
 impl eframe::App for LoopahApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.poll_loader();
+        self.poll_analysis();
+        self.poll_separation();
+        self.poll_denoise();
+        self.sync_active_loop();
+        self.capture_window(ctx);
+        self.save_session(false);
+        self.handle_view_keys(ctx);
+        self.follow_playhead();
+        if self.player.as_ref().is_some_and(|p| p.is_playing()) {
+            // Keep the playhead (and follow) moving without user input.
+            ctx.request_repaint();
+        }
 
+        let mut skip_silence = false;
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 if ui.button("Open audio…").clicked() {
@@ -180,14 +629,73 @@ impl eframe::App for LoopahApp {
                         .pick_file();
 
                     if let Some(path) = picked {
-                        self.reset_state();
-                        self.selected_file = Some(path.clone());
-                        let (events, stream_rx) = spawn_decode_job(path);
-                        self.load_events = Some(events);
-                        self.stream_rx = Some(stream_rx);
+                        self.open_file(path);
                     }
                 }
 
+                ui.menu_button("Project", |ui| {
+                    if ui.button("Open project…").clicked() {
+                        ui.close();
+                        self.open_project();
+                    }
+                    if ui
+                        .add_enabled(self.info.is_some(), egui::Button::new("Save project…"))
+                        .clicked()
+                    {
+                        ui.close();
+                        self.save_project();
+                    }
+                });
+
+                ui.menu_button("Markers", |ui| {
+                    let loaded = self.info.is_some();
+                    let has_markers = !self.library.loops.is_empty()
+                        || self.library.cues.iter().any(Option::is_some);
+                    for format in MarkerFormat::ALL {
+                        if ui
+                            .add_enabled(
+                                loaded,
+                                egui::Button::new(format!("Import {}…", format.label())),
+                            )
+                            .clicked()
+                        {
+                            ui.close();
+                            self.import_markers(format);
+                        }
+                    }
+                    ui.separator();
+                    for format in MarkerFormat::ALL {
+                        if ui
+                            .add_enabled(
+                                has_markers,
+                                egui::Button::new(format!("Export {}…", format.label())),
+                            )
+                            .clicked()
+                        {
+                            ui.close();
+                            self.export_markers(format);
+                        }
+                    }
+                });
+
+                ui.menu_button("Export", |ui| {
+                    let ready = self.pitch_track.is_some();
+                    if ui
+                        .add_enabled(ready, egui::Button::new("Loop notes as MIDI…"))
+                        .clicked()
+                    {
+                        ui.close();
+                        self.export_midi(false);
+                    }
+                    if ui
+                        .add_enabled(ready, egui::Button::new("All notes as MIDI…"))
+                        .clicked()
+                    {
+                        ui.close();
+                        self.export_midi(true);
+                    }
+                });
+
                 if let Some(p) = &self.selected_file {
                     ui.label(p.display().to_string());
                 } else {
@@ -202,18 +710,190 @@ impl eframe::App for LoopahApp {
                             player.pause();
                         } else {
                             player.play();
+                            self.follow_paused = false;
                         }
                     }
                     if ui.button("Stop").clicked() {
                         player.stop();
                     }
+                    if ui
+                        .add_enabled(
+                            self.info.as_ref().is_some_and(|i| !i.silences.is_empty()),
+                            egui::Button::new("Skip silence"),
+                        )
+                        .on_hover_text("Jump to where sound next starts (N)")
+                        .clicked()
+                    {
+                        skip_silence = true;
+                    }
                 } else {
                     ui.add_enabled(false, egui::Button::new("Play"));
                     ui.add_enabled(false, egui::Button::new("Stop"));
                 }
+                let freeze = ui
+                    .add_enabled(
+                        self.mem_audio.is_some(),
+                        egui::Button::selectable(self.frozen, "Freeze"),
+                    )
+                    .on_hover_text("Hold the sound at the playhead as a drone (F)");
+                if freeze.clicked() {
+                    self.toggle_freeze();
+                }
+                ui.checkbox(&mut self.scrub_audio, "Scrub audio")
+                    .on_hover_text("Play short snippets while dragging the playhead");
+                ui.separator();
+                let speed = ui
+                    .add(
+                        egui::DragValue::new(&mut self.speed)
+                            .speed(0.01)
+                            .range(0.25..=2.0)
+                            .prefix("Speed ×")
+                            .max_decimals(2),
+                    )
+                    .on_hover_text("Playback tempo; pitch is kept");
+                if speed.changed()
+                    && let Some(player) = &self.player
+                {
+                    player.set_speed(self.speed as f64);
+                }
+                let volume = ui
+                    .add(
+                        egui::Slider::new(&mut self.volume, 0.0..=1.5)
+                            .show_value(false)
+                            .text("Vol"),
+                    )
+                    .on_hover_text("Playback volume");
+                if volume.changed()
+                    && let Some(player) = &self.player
+                {
+                    player.set_volume(self.volume);
+                }
+                let pitch = ui
+                    .add(
+                        egui::DragValue::new(&mut self.pitch_semitones)
+                            .speed(0.05)
+                            .range(-12.0..=12.0)
+                            .prefix("Pitch ")
+                            .suffix(" st")
+                            .max_decimals(2),
+                    )
+                    .on_hover_text("Transpose playback in semitones; double-click to type");
+                if pitch.changed()
+                    && let Some(player) = &self.player
+                {
+                    player.set_pitch_semitones(self.pitch_semitones);
+                }
+                ui.separator();
+                if self.stems.is_some() {
+                    let mut changed = false;
+                    egui::ComboBox::from_label("Source")
+                        .selected_text(self.source.label())
+                        .show_ui(ui, |ui| {
+                            for source in [
+                                PlaybackSource::Original,
+                                PlaybackSource::Harmonic,
+                                PlaybackSource::Percussive,
+                                PlaybackSource::Blend,
+                            ] {
+                                changed |= ui
+                                    .selectable_value(&mut self.source, source, source.label())
+                                    .changed();
+                            }
+                        });
+                    if self.source == PlaybackSource::Blend {
+                        changed |= ui
+                            .add(
+                                egui::Slider::new(&mut self.stem_balance, 0.0..=1.0)
+                                    .show_value(false)
+                                    .text("Harm ↔ Perc"),
+                            )
+                            .changed();
+                    }
+                    if changed {
+                        self.sync_stem_mix();
+                    }
+                } else if self.separation_events.is_some() {
+                    ui.spinner();
+                    ui.label("Separating…");
+                } else if ui
+                    .add_enabled(self.mem_audio.is_some(), egui::Button::new("Separate"))
+                    .on_hover_text("Split into harmonic and percussive stems")
+                    .clicked()
+                    && let Some(audio) = self.selected_audio().cloned()
+                {
+                    // Stems replace the source while playing, so they keep the denoise.
+                    self.stems_denoised = self.denoised.is_some() && self.use_denoised;
+                    self.separation_events = Some(spawn_separation_job(audio));
+                }
+                ui.separator();
+                if self.denoise_events.is_some() {
+                    ui.spinner();
+                    ui.label("Denoising…");
+                } else {
+                    let stems_playing =
+                        self.stems.is_some() && self.source != PlaybackSource::Original;
+                    if self.denoised.is_some() && stems_playing {
+                        // The stems carry whichever audio they were separated from.
+                        let mut denoised = self.stems_denoised;
+                        ui.add_enabled(false, egui::Checkbox::new(&mut denoised, "Denoised"))
+                            .on_disabled_hover_text(
+                                "The stems were separated from this audio; switch Source to \
+                                 Original to change it",
+                            );
+                    } else if self.denoised.is_some()
+                        && ui
+                            .checkbox(&mut self.use_denoised, "Denoised")
+                            .on_hover_text("Play the denoised audio")
+                            .changed()
+                    {
+                        self.sync_source();
+                    }
+                    let mut noise = None;
+                    ui.add_enabled_ui(self.mem_audio.is_some(), |ui| {
+                        ui.menu_button("Denoise", |ui| {
+                            ui.add(
+                                egui::Slider::new(&mut self.denoise_strength, 0.5..=3.0)
+                                    .text("Strength"),
+                            );
+                            if ui
+                                .button("Auto noise profile")
+                                .on_hover_text("Learn the noise from the quietest moments")
+                                .clicked()
+                            {
+                                ui.close();
+                                noise = Some(NoiseSource::Auto);
+                            }
+                            if ui
+                                .add_enabled(
+                                    self.loop_range.is_some(),
+                                    egui::Button::new("Noise from loop"),
+                                )
+                                .on_hover_text(
+                                    "Learn the noise from A–B; select a passage of hiss only",
+                                )
+                                .clicked()
+                            {
+                                ui.close();
+                                noise =
+                                    self.loop_range.map(|r| NoiseSource::Region(r.start, r.end));
+                            }
+                        });
+                    });
+                    if let (Some(source), Some(audio)) = (noise, &self.mem_audio) {
+                        self.denoise_events = Some(spawn_denoise_job(
+                            audio.clone(),
+                            source,
+                            self.denoise_strength,
+                        ));
+                    }
+                }
             });
         });
 
+        if skip_silence {
+            self.skip_silence();
+        }
+
         egui::TopBottomPanel::top("loop-controls").show(ctx, |ui| {
             if let (Some(info), Some(loop_range)) = (self.info.as_ref(), self.loop_range) {
                 let duration = file_duration_seconds(info);
@@ -253,6 +933,10 @@ impl eframe::App for LoopahApp {
                         start += ten_secs;
                         changed = true;
                     }
+                    if let Some(t) = onset_buttons(ui, &self.onsets, start, "Shift") {
+                        start = t;
+                        changed = true;
+                    }
 
                     ui.separator();
 
@@ -282,14 +966,60 @@ impl eframe::App for LoopahApp {
                         end += ten_secs;
                         changed = true;
                     }
+                    if let Some(t) = onset_buttons(ui, &self.onsets, end, "Alt") {
+                        end = t;
+                        changed = true;
+                    }
 
                     ui.separator();
                     ui.label(format!("Len: {}", format_time(loop_range.duration())));
                 });
 
+                if let Some(map) = &self.beat_grid {
+                    ui.horizontal(|ui| {
+                        ui.label("Bars");
+                        let (first, last) = &mut self.loop_bars;
+                        ui.add(egui::DragValue::new(first).range(-999..=9999));
+                        ui.label("to");
+                        ui.add(egui::DragValue::new(last).range(*first..=9999));
+                        if ui
+                            .button("Loop bars")
+                            .on_hover_text("Set A/B to these bars, following the tempo map")
+                            .clicked()
+                        {
+                            start = map.time_of_bar(*first);
+                            end = map.time_of_bar(*last + 1);
+                            changed = true;
+                        }
+                    });
+                }
+
+                if !self.sections.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("Sections");
+                        for section in &self.sections {
+                            let label = egui::RichText::new(&section.label)
+                                .color(section_color(section.group));
+                            if ui
+                                .small_button(label)
+                                .on_hover_text(format!(
+                                    "Loop {} – {}",
+                                    format_time(section.start),
+                                    format_time(section.end)
+                                ))
+                                .clicked()
+                            {
+                                start = section.start;
+                                end = section.end;
+                                changed = true;
+                            }
+                        }
+                    });
+                }
+
                 ui.label(
                     egui::RichText::new(
-                        "TIP: Hold Shift and drag on the waveform to set A/B quickly.",
+                        "TIP: Hold Shift and drag on the waveform to set A/B quickly (Ctrl to skip snapping). Click to seek; Shift+1…9 sets a cue, 1…9 jumps to it.",
                     )
                     .size(15.0)
                     .strong(),
@@ -304,38 +1034,356 @@ impl eframe::App for LoopahApp {
             }
         });
 
+        if self.info.is_some() {
+            let mut action = None;
+            egui::SidePanel::left("loop-library")
+                .default_width(200.0)
+                .show(ctx, |ui| {
+                    action = draw_loop_sidebar(ui, &mut self.library);
+                });
+            match action {
+                Some(LoopAction::Add) => {
+                    if let Some(range) = self.loop_range {
+                        self.library.add(
+                            range.start,
+                            range.end,
+                            self.speed,
+                            self.pitch_semitones,
+                            self.volume,
+                        );
+                    }
+                }
+                Some(LoopAction::Activate(i)) => self.activate_loop(i),
+                Some(LoopAction::Remove(i)) => self.library.remove(i),
+                Some(LoopAction::Move(i, up)) => self.library.move_loop(i, up),
+                None => {}
+            }
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             if self.info.is_some() {
-                let (res, duration_for_interaction) = {
+                let mut zoom_action = None;
+                let mut tap_clicked = false;
+                let mut downbeat_clicked = false;
+                let mut tempo_changed = false;
+                let duration_for_interaction = {
                     let info = self.info.as_ref().unwrap();
                     let duration = file_duration_seconds(info);
-                    ui.label(format!(
-                        "Rate: {} Hz | Ch: {} | Frames: {} | Preview: {} buckets",
-                        info.sample_rate,
-                        info.channels,
-                        info.total_frames,
-                        info.rms_preview.len()
-                    ));
+                    ui.horizontal(|ui| {
+                        ui.label("Zoom");
+                        if ui.small_button("−").on_hover_text("Zoom out (-)").clicked() {
+                            zoom_action = Some(ZoomAction::Out);
+                        }
+                        if ui.small_button("+").on_hover_text("Zoom in (+)").clicked() {
+                            zoom_action = Some(ZoomAction::In);
+                        }
+                        if ui
+                            .small_button("Loop")
+                            .on_hover_text("Zoom to loop (Z)")
+                            .clicked()
+                        {
+                            zoom_action = Some(ZoomAction::ToLoop);
+                        }
+                        if ui
+                            .small_button("Fit")
+                            .on_hover_text("Zoom to fit (0)")
+                            .clicked()
+                        {
+                            zoom_action = Some(ZoomAction::Fit);
+                        }
+                        ui.separator();
+                        ui.label("Channels");
+                        for lanes in [
+                            WaveformLanes::Mixed,
+                            WaveformLanes::Channels,
+                            WaveformLanes::MidSide,
+                        ] {
+                            ui.selectable_value(&mut self.waveform_lanes, lanes, lanes.label());
+                        }
+                        ui.separator();
+                        ui.checkbox(&mut self.show_chords, "Chords");
+                        ui.checkbox(&mut self.show_sections, "Sections")
+                            .on_hover_text("Repeated passages as coloured bands");
+                        ui.checkbox(&mut self.show_spectrogram, "Spectrogram");
+                        ui.checkbox(&mut self.show_pitch, "Pitch")
+                            .on_hover_text("Melody pitch contour on the spectrogram");
+                        ui.separator();
+                        let follow_label =
+                            if self.follow_paused && self.follow_mode != FollowMode::Off {
+                                format!("{} (paused)", self.follow_mode.label())
+                            } else {
+                                self.follow_mode.label().to_string()
+                            };
+                        egui::ComboBox::from_label("Follow")
+                            .selected_text(follow_label)
+                            .show_ui(ui, |ui| {
+                                for mode in
+                                    [FollowMode::Off, FollowMode::Page, FollowMode::Continuous]
+                                {
+                                    ui.selectable_value(&mut self.follow_mode, mode, mode.label());
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.show_beat_grid, "Beat grid");
+                        if let Some(grid) = self.beat_grid.as_mut() {
+                            let mut bpm = grid.mean_bpm();
+                            if ui
+                                .add(
+                                    egui::DragValue::new(&mut bpm)
+                                        .speed(0.05)
+                                        .range(20.0..=400.0)
+                                        .suffix(" BPM")
+                                        .max_decimals(2),
+                                )
+                                .on_hover_text(
+                                    "Average tempo; editing replaces the map with a constant tempo",
+                                )
+                                .changed()
+                            {
+                                *grid = TempoMap::constant(
+                                    bpm,
+                                    grid.downbeat_time(),
+                                    grid.beats_per_bar,
+                                    duration,
+                                );
+                                tempo_changed = true;
+                            }
+                            tempo_changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut grid.beats_per_bar)
+                                        .range(1..=16)
+                                        .suffix(" beats/bar"),
+                                )
+                                .changed();
+                            if let Some(player) = &self.player {
+                                ui.label(format!(
+                                    "♩ {:.1} here",
+                                    grid.bpm_at(player.position_seconds())
+                                ));
+                            }
+                            if grid.has_warps()
+                                && ui
+                                    .small_button("Clear warps")
+                                    .on_hover_text("Drop all warp markers (Alt+drag a beat to add)")
+                                    .clicked()
+                            {
+                                grid.clear_warps();
+                                tempo_changed = true;
+                            }
+                        } else {
+                            ui.label("Detecting tempo…");
+                        }
+                        tap_clicked = ui
+                            .button("Tap")
+                            .on_hover_text("Tap along to set the tempo (T)")
+                            .clicked();
+                        downbeat_clicked = ui
+                            .add_enabled(
+                                self.beat_grid.is_some(),
+                                egui::Button::new("Downbeat here"),
+                            )
+                            .on_hover_text(
+                                "Make the beat nearest the playhead bar 1; drag the orange line to move it",
+                            )
+                            .clicked();
+                        ui.separator();
+                        egui::ComboBox::from_label("Snap")
+                            .selected_text(self.snap_mode.label())
+                            .show_ui(ui, |ui| {
+                                for mode in
+                                    [SnapMode::Off, SnapMode::Beat, SnapMode::Bar, SnapMode::Onset]
+                                {
+                                    ui.selectable_value(&mut self.snap_mode, mode, mode.label());
+                                }
+                            })
+                            .response
+                            .on_hover_text("Hold Ctrl while dragging to place markers freely");
+                        ui.checkbox(&mut self.snap_zero_crossings, "Zero crossings")
+                            .on_hover_text("Nudge loop markers onto the nearest zero crossing");
+                        ui.separator();
+                        tempo_changed |= ui
+                            .checkbox(&mut self.metronome, "Metronome")
+                            .on_hover_text("Click on every beat of the map during playback")
+                            .changed();
+                    });
+                    let mut retune = false;
+                    let mut status = vec![
+                        format!("Ch: {}", info.channels),
+                        format!("Frames: {}", info.total_frames),
+                        format!("Peak levels: {}", info.peaks.levels.len()),
+                    ];
+                    if let Some(chords) = &self.chords {
+                        let key = chords.key.transposed(self.pitch_semitones.round() as i32);
+                        status.push(format!("Key: {}", key.key_name()));
+                    }
+                    if let (Some(track), Some(player)) = (&self.pitch_track, &self.player)
+                        && self.show_pitch
+                        && let Some(midi) = track.at(player.position_seconds())
+                    {
+                        // Describe what is heard, i.e. including the playback transposition.
+                        let heard = midi + self.pitch_semitones;
+                        status.push(format!("Pitch: {}", describe_pitch(heard)));
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Rate: {} Hz", info.sample_rate));
+                        if let Some(cents) = self.tuning_cents {
+                            let tuning = match cents.round() {
+                                0.0 => "(in tune)".to_owned(),
+                                c if c > 0.0 => format!("({c:+.0} ¢ sharp)"),
+                                c => format!("({c:+.0} ¢ flat)"),
+                            };
+                            ui.label(tuning)
+                                .on_hover_text("Tuning offset of the recording from A4 = 440 Hz");
+                            if cents.round() != 0.0 {
+                                retune = ui
+                                    .small_button("Correct")
+                                    .on_hover_text("Shift playback pitch to cancel the offset")
+                                    .clicked();
+                            }
+                        }
+                        ui.label("|");
+                        ui.label(status.join(" | "));
+                    });
+                    // A project that failed to open leaves the current file loaded.
+                    if let Some(err) = &self.load_error {
+                        ui.colored_label(egui::Color32::RED, format!("Failed to load: {err}"));
+                    }
+                    if retune && let Some(cents) = self.tuning_cents {
+                        // Keep any whole-semitone transposition the user already set.
+                        self.pitch_semitones = self.pitch_semitones.round() - cents / 100.0;
+                        if let Some(player) = &self.player {
+                            player.set_pitch_semitones(self.pitch_semitones);
+                        }
+                    }
                     ui.add_space(6.0);
                     let playhead = self.player.as_ref().map(|p| p.position_seconds());
                     let loop_range = self.loop_range.map(|r| (r.start, r.end));
-                    (
-                        draw_waveform(
+                    let overview = draw_overview(
+                        ui,
+                        info,
+                        self.view_x_min,
+                        self.view_x_max,
+                        playhead,
+                        loop_range,
+                    );
+                    if let Some((start, end)) = overview.view {
+                        self.view_x_min = start;
+                        self.view_x_max = end;
+                        self.follow_paused = true;
+                    }
+                    duration
+                };
+
+                if self.show_chords
+                    && let Some(timeline) = &self.chords
+                {
+                    ui.add_space(4.0);
+                    let res = draw_chord_lane(
+                        ui,
+                        ChordLaneView {
+                            timeline,
+                            duration_s: duration_for_interaction,
+                            x_min: self.view_x_min,
+                            x_max: self.view_x_max,
+                            playhead_sec: self.player.as_ref().map(|p| p.position_seconds()),
+                            transpose: self.pitch_semitones.round() as i32,
+                            allow_pan: self.marker_drag.is_none(),
+                        },
+                    );
+                    self.apply_timeline_result(duration_for_interaction, &res);
+                }
+
+                if !self.library.loops.is_empty() {
+                    ui.add_space(4.0);
+                    let (mut res, hit) = draw_loop_lanes(
+                        ui,
+                        LoopLanesView {
+                            library: &self.library,
+                            duration_s: duration_for_interaction,
+                            x_min: self.view_x_min,
+                            x_max: self.view_x_max,
+                            playhead_sec: self.player.as_ref().map(|p| p.position_seconds()),
+                            allow_pan: self.marker_drag.is_none(),
+                        },
+                    );
+                    if let Some(i) = hit {
+                        // Selecting a loop replaces the seek a click would do.
+                        res.clicked = false;
+                        self.activate_loop(i);
+                    }
+                    self.apply_timeline_result(duration_for_interaction, &res);
+                }
+
+                ui.add_space(4.0);
+                let res = {
+                    let info = self.info.as_ref().unwrap();
+                    let playhead = self.player.as_ref().map(|p| p.position_seconds());
+                    let loop_range = self.loop_range.map(|r| (r.start, r.end));
+                    draw_waveform(
                             ui,
-                            info,
-                            self.view_x_min,
-                            self.view_x_max,
-                            playhead,
-                            loop_range,
-                        ),
-                        duration,
-                    )
+                            WaveformView {
+                                info,
+                                audio: self.mem_audio.as_deref(),
+                                x_min: self.view_x_min,
+                                x_max: self.view_x_max,
+                                playhead_sec: playhead,
+                                loop_range,
+                                allow_pan: self.marker_drag.is_none(),
+                                lanes: self.waveform_lanes,
+                                beat_grid: self.beat_grid.as_ref().filter(|_| self.show_beat_grid),
+                                onsets: &self.onsets,
+                                sections: if self.show_sections {
+                                    &self.sections
+                                } else {
+                                    &[]
+                                },
+                                cues: &self.library.cues,
+                                cache: &mut self.waveform_cache,
+                            },
+                        )
                 };
-                self.view_x_min = res.x_min;
-                self.view_x_max = res.x_max;
-                self.handle_waveform_interaction(duration_for_interaction, &res);
+                self.apply_timeline_result(duration_for_interaction, &res);
+
+                if self.show_spectrogram {
+                    ui.add_space(4.0);
+                    if let Some(spec) = &self.spectrogram {
+                        let res = draw_spectrogram(
+                            ui,
+                            SpectrogramView {
+                                spec,
+                                duration_s: duration_for_interaction,
+                                x_min: self.view_x_min,
+                                x_max: self.view_x_max,
+                                playhead_sec: self.player.as_ref().map(|p| p.position_seconds()),
+                                loop_range: self.loop_range.map(|r| (r.start, r.end)),
+                                allow_pan: self.marker_drag.is_none(),
+                                pitch: self.pitch_track.as_ref().filter(|_| self.show_pitch),
+                                cache: &mut self.spectrogram_cache,
+                            },
+                        );
+                        self.apply_timeline_result(duration_for_interaction, &res);
+                    } else {
+                        ui.label("Computing spectrogram…");
+                    }
+                }
+                if let Some(action) = zoom_action {
+                    self.apply_zoom_action(action);
+                }
+                if tap_clicked {
+                    self.tap_tempo(ctx.input(|i| i.time));
+                }
+                if downbeat_clicked
+                    && let (Some(grid), Some(player)) = (self.beat_grid.as_mut(), &self.player)
+                {
+                    grid.set_downbeat_near(player.position_seconds());
+                    tempo_changed = true;
+                }
+                if tempo_changed {
+                    self.sync_metronome();
+                }
             } else if let Some(err) = &self.load_error {
-                ui.colored_label(egui::Color32::RED, format!("Failed to load audio: {err}"));
+                ui.colored_label(egui::Color32::RED, format!("Failed to load: {err}"));
             } else if let (Some(sr), Some(ch)) = (self.meta_sample_rate, self.meta_channels) {
                 ui.label(format!("Loading preview… {} Hz | Ch: {}", sr, ch));
             } else {
@@ -343,24 +1391,54 @@ impl eframe::App for LoopahApp {
             }
         });
     }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_session(true);
+    }
 }
 
 impl LoopahApp {
+    /// Apply pan/zoom and pointer interaction reported by any timeline plot.
+    fn apply_timeline_result(&mut self, duration: f64, result: &WaveformResult) {
+        let panned = result.x_min != self.view_x_min || result.x_max != self.view_x_max;
+        if panned && result.drag_active && self.marker_drag.is_none() && !result.shift_down {
+            self.follow_paused = true;
+        }
+        self.view_x_min = result.x_min;
+        self.view_x_max = result.x_max;
+        if result.zoom_delta != 1.0 {
+            let anchor = result
+                .pointer_seconds
+                .unwrap_or((self.view_x_min + self.view_x_max) * 0.5);
+            self.zoom_view(result.zoom_delta, anchor);
+        }
+        self.handle_waveform_interaction(duration, result);
+    }
+
     fn handle_waveform_interaction(&mut self, duration: f64, result: &WaveformResult) {
+        let snap = |app: &Self, t: f64| {
+            if result.command_down {
+                t
+            } else {
+                app.snap_time(t)
+            }
+        };
         if result.shift_down {
-            self.marker_drag = None;
-            if result.drag_started {
-                if let Some(sec) = result.pointer_seconds {
-                    self.loop_drag_anchor = Some(sec);
-                    self.loop_range = Some(LoopRange::ordered(sec, sec).clamp(duration));
-                    self.sync_player_loop();
-                }
+            self.end_marker_drag();
+            if result.drag_started
+                && let Some(sec) = result.pointer_seconds
+            {
+                let sec = snap(self, sec);
+                self.loop_drag_anchor = Some(sec);
+                self.loop_range = Some(LoopRange::ordered(sec, sec).clamp(duration));
+                self.sync_player_loop();
             }
-            if let (Some(anchor), Some(current)) = (self.loop_drag_anchor, result.pointer_seconds) {
-                if result.drag_active {
-                    self.loop_range = Some(LoopRange::ordered(anchor, current).clamp(duration));
-                    self.sync_player_loop();
-                }
+            if let (Some(anchor), Some(current)) = (self.loop_drag_anchor, result.pointer_seconds)
+                && result.drag_active
+            {
+                let current = snap(self, current);
+                self.loop_range = Some(LoopRange::ordered(anchor, current).clamp(duration));
+                self.sync_player_loop();
             }
             if result.drag_released {
                 self.loop_drag_anchor = None;
@@ -368,41 +1446,622 @@ impl LoopahApp {
             return;
         }
 
-        // Non-shift interactions: drag individual markers if near pointer.
+        // Plain click: move the playhead there.
+        if result.clicked
+            && let (Some(player), Some(pointer)) = (&self.player, result.pointer_seconds)
+        {
+            player.set_position_seconds(pointer.clamp(0.0, duration));
+        }
+
+        // Non-shift drags: grab a marker or the playhead if near the pointer.
         if result.drag_started {
             self.loop_drag_anchor = None;
-            if let (Some(range), Some(pointer)) = (self.loop_range, result.pointer_seconds) {
-                let threshold = (duration * 0.01).max(0.1);
-                let dist_start = (pointer - range.start).abs();
-                let dist_end = (pointer - range.end).abs();
-                if dist_start <= dist_end && dist_start <= threshold {
-                    self.marker_drag = Some(MarkerHandle::Start);
-                } else if dist_end < dist_start && dist_end <= threshold {
-                    self.marker_drag = Some(MarkerHandle::End);
-                } else if dist_end == dist_start && dist_start <= threshold {
-                    self.marker_drag = Some(MarkerHandle::End);
-                } else {
-                    self.marker_drag = None;
-                }
+            if let Some(pointer) = result.pointer_seconds {
+                // ~1% of the visible window, so handles stay grabbable when zoomed.
+                let threshold = ((self.view_x_max - self.view_x_min) * 0.01).max(1e-4);
+                self.marker_drag = self.nearest_handle(pointer, threshold, result.alt_down);
             }
         }
 
-        if let (Some(handle), Some(pointer)) = (self.marker_drag, result.pointer_seconds) {
-            if result.drag_active || result.drag_released {
-                if let Some(mut range) = self.loop_range {
-                    match handle {
-                        MarkerHandle::Start => range.start = pointer,
-                        MarkerHandle::End => range.end = pointer,
+        if let (Some(handle), Some(pointer)) = (self.marker_drag, result.pointer_seconds)
+            && (result.drag_active || result.drag_released)
+        {
+            match handle {
+                MarkerHandle::Playhead => {
+                    let pointer = pointer.clamp(0.0, duration);
+                    if let Some(player) = &self.player {
+                        player.set_position_seconds(pointer);
+                        if self.scrub_audio && !result.drag_released {
+                            player.set_scrub(Some(pointer));
+                        }
+                    }
+                }
+                MarkerHandle::Beat(beat) => {
+                    if let Some(grid) = self.beat_grid.as_mut() {
+                        grid.warp(beat, pointer.clamp(0.0, duration));
+                    }
+                    if result.drag_released {
+                        self.sync_metronome();
+                    }
+                }
+                MarkerHandle::Start | MarkerHandle::End => {
+                    if let Some(mut range) = self.loop_range {
+                        let pointer = snap(self, pointer);
+                        match handle {
+                            MarkerHandle::Start => range.start = pointer,
+                            _ => range.end = pointer,
+                        }
+                        self.loop_range =
+                            Some(LoopRange::ordered(range.start, range.end).clamp(duration));
+                        self.sync_player_loop();
                     }
-                    self.loop_range =
-                        Some(LoopRange::ordered(range.start, range.end).clamp(duration));
-                    self.sync_player_loop();
                 }
             }
         }
 
         if result.drag_released {
-            self.marker_drag = None;
+            self.end_marker_drag();
+        }
+    }
+
+    /// Closest draggable handle within `threshold` seconds. Loop markers win ties
+    /// with the playhead (B over A), so a stopped playhead never hides marker A;
+    /// beat lines lose every tie. With `alt`, any tracked beat is grabbable.
+    fn nearest_handle(&self, pointer: f64, threshold: f64, alt: bool) -> Option<MarkerHandle> {
+        let mut candidates = Vec::with_capacity(5);
+        if let Some(grid) = self.beat_grid.as_ref().filter(|_| self.show_beat_grid) {
+            let beat = grid.beat_at(pointer).round() as i64;
+            if alt && beat != 0 && grid.is_warpable(beat) {
+                candidates.push((MarkerHandle::Beat(beat), grid.time_of_beat(beat)));
+            }
+            candidates.push((MarkerHandle::Beat(0), grid.downbeat_time()));
+        }
+        if let Some(player) = &self.player {
+            candidates.push((MarkerHandle::Playhead, player.position_seconds()));
+        }
+        if let Some(range) = self.loop_range {
+            candidates.push((MarkerHandle::Start, range.start));
+            candidates.push((MarkerHandle::End, range.end));
+        }
+
+        let mut best: Option<(MarkerHandle, f64)> = None;
+        for (handle, sec) in candidates {
+            let dist = (pointer - sec).abs();
+            if dist <= threshold && best.is_none_or(|(_, d)| dist <= d) {
+                best = Some((handle, dist));
+            }
+        }
+        best.map(|(handle, _)| handle)
+    }
+
+    /// Move a loop marker time onto the nearest snap target for `snap_mode`,
+    /// then onto a nearby zero crossing if enabled.
+    fn snap_time(&self, t: f64) -> f64 {
+        let target = match self.snap_mode {
+            SnapMode::Off => None,
+            SnapMode::Beat => self
+                .beat_grid
+                .as_ref()
+                .map(|map| map.time_of_beat(map.beat_at(t).round() as i64)),
+            SnapMode::Bar => self.beat_grid.as_ref().map(|map| {
+                let bpb = map.beats_per_bar.max(1) as f64;
+                map.time_of_beat(((map.beat_at(t) / bpb).round() * bpb) as i64)
+            }),
+            SnapMode::Onset => {
+                let i = self.onsets.partition_point(|o| *o < t);
+                [i.checked_sub(1), Some(i)]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|i| self.onsets.get(i).copied())
+                    .min_by(|a, b| (a - t).abs().total_cmp(&(b - t).abs()))
+            }
+        };
+        let mut t = target.unwrap_or(t);
+        if self.snap_zero_crossings
+            && let Some(audio) = &self.mem_audio
+        {
+            let sr = audio.sample_rate as f64;
+            let radius = (ZERO_CROSSING_SECONDS * sr) as usize;
+            let frame = (t * sr).round().max(0.0) as usize;
+            if let Some(f) = audio.nearest_zero_crossing(frame, radius) {
+                t = f as f64 / sr;
+            }
+        }
+        t
+    }
+
+    fn end_marker_drag(&mut self) {
+        if matches!(self.marker_drag, Some(MarkerHandle::Playhead))
+            && let Some(player) = &self.player
+        {
+            player.set_scrub(None);
+        }
+        self.marker_drag = None;
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ZoomAction {
+    In,
+    Out,
+    ToLoop,
+    Fit,
+}
+
+impl LoopahApp {
+    fn handle_view_keys(&mut self, ctx: &egui::Context) {
+        if self.info.is_none() || ctx.wants_keyboard_input() {
+            return;
+        }
+        let action = ctx.input(|i| {
+            if i.key_pressed(egui::Key::Plus) || i.key_pressed(egui::Key::Equals) {
+                Some(ZoomAction::In)
+            } else if i.key_pressed(egui::Key::Minus) {
+                Some(ZoomAction::Out)
+            } else if i.key_pressed(egui::Key::Z) {
+                Some(ZoomAction::ToLoop)
+            } else if i.key_pressed(egui::Key::Num0) {
+                Some(ZoomAction::Fit)
+            } else {
+                None
+            }
+        });
+        if let Some(action) = action {
+            self.apply_zoom_action(action);
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::T)) {
+            self.tap_tempo(ctx.input(|i| i.time));
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::F)) {
+            self.toggle_freeze();
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::N)) {
+            self.skip_silence();
+        }
+        if let Some((slot, store)) = ctx.input(cue_key) {
+            self.hot_cue(slot, store);
+        }
+        let onset_jump = ctx.input(|i| {
+            let forward = if i.key_pressed(egui::Key::ArrowRight) {
+                true
+            } else if i.key_pressed(egui::Key::ArrowLeft) {
+                false
+            } else {
+                return None;
+            };
+            let target = if i.modifiers.shift {
+                OnsetTarget::Start
+            } else if i.modifiers.alt {
+                OnsetTarget::End
+            } else {
+                OnsetTarget::Playhead
+            };
+            Some((target, forward))
+        });
+        if let Some((target, forward)) = onset_jump {
+            self.jump_to_onset(target, forward);
+        }
+    }
+
+    /// Make saved loop `index` the A/B range with its playback settings, or
+    /// detach A/B from it when it already is the active one.
+    fn activate_loop(&mut self, index: usize) {
+        if self.library.active == Some(index) {
+            self.library.active = None;
+            return;
+        }
+        let Some(lp) = self.library.loops.get(index).cloned() else {
+            return;
+        };
+        self.library.active = Some(index);
+        let duration = self.info.as_ref().map(file_duration_seconds).unwrap_or(0.0);
+        self.loop_range = Some(LoopRange::ordered(lp.start, lp.end).clamp(duration));
+        self.speed = lp.speed;
+        self.pitch_semitones = lp.pitch;
+        self.volume = lp.volume;
+        self.sync_player_loop();
+        if let Some(player) = &self.player {
+            player.set_speed(lp.speed as f64);
+            player.set_pitch_semitones(lp.pitch);
+            player.set_volume(lp.volume);
+            player.set_position_seconds(lp.start);
+        }
+        self.follow_paused = false;
+    }
+
+    /// The active saved loop follows A/B and playback setting edits.
+    fn sync_active_loop(&mut self) {
+        let (Some(range), speed, pitch, volume) = (
+            self.loop_range,
+            self.speed,
+            self.pitch_semitones,
+            self.volume,
+        ) else {
+            return;
+        };
+        if let Some(lp) = self.library.active_mut() {
+            lp.start = range.start;
+            lp.end = range.end;
+            lp.speed = speed;
+            lp.pitch = pitch;
+            lp.volume = volume;
+        }
+    }
+
+    /// Store the playhead in cue `slot`, or jump to it when set.
+    fn hot_cue(&mut self, slot: usize, store: bool) {
+        let Some(player) = self.player.as_ref() else {
+            return;
+        };
+        if store {
+            self.library.cues[slot] = Some(player.position_seconds());
+        } else if let Some(t) = self.library.cues[slot] {
+            player.set_position_seconds(t);
+            self.follow_paused = false;
+        }
+    }
+
+    /// Move the playhead past the current or next silent region.
+    fn skip_silence(&mut self) {
+        let (Some(info), Some(player)) = (self.info.as_ref(), self.player.as_ref()) else {
+            return;
+        };
+        if let Some(t) = info.next_audible(player.position_seconds()) {
+            player.set_position_seconds(t);
+            self.follow_paused = false;
+        }
+    }
+
+    /// Start or release the spectral freeze drone.
+    fn toggle_freeze(&mut self) {
+        let Some(player) = self.player.as_ref() else {
+            return;
+        };
+        let on = !self.frozen;
+        // Streamed playback ignores the freeze until the full decode lands.
+        if player.set_freeze(on) {
+            self.frozen = on;
+        }
+    }
+
+    /// Move the playhead, A or B to the next or previous detected onset.
+    fn jump_to_onset(&mut self, target: OnsetTarget, forward: bool) {
+        let Some(info) = self.info.as_ref() else {
+            return;
+        };
+        let duration = file_duration_seconds(info);
+        match target {
+            OnsetTarget::Playhead => {
+                if let Some(player) = &self.player
+                    && let Some(t) =
+                        adjacent_onset(&self.onsets, player.position_seconds(), forward)
+                {
+                    player.set_position_seconds(t);
+                }
+            }
+            OnsetTarget::Start | OnsetTarget::End => {
+                let Some(mut range) = self.loop_range else {
+                    return;
+                };
+                let marker = match target {
+                    OnsetTarget::Start => &mut range.start,
+                    _ => &mut range.end,
+                };
+                let Some(t) = adjacent_onset(&self.onsets, *marker, forward) else {
+                    return;
+                };
+                *marker = t;
+                self.loop_range = Some(LoopRange::ordered(range.start, range.end).clamp(duration));
+                self.sync_player_loop();
+            }
+        }
+    }
+
+    fn apply_zoom_action(&mut self, action: ZoomAction) {
+        let Some(info) = self.info.as_ref() else {
+            return;
+        };
+        let duration = file_duration_seconds(info);
+        let center = (self.view_x_min + self.view_x_max) * 0.5;
+        match action {
+            ZoomAction::In => self.zoom_view(ZOOM_STEP, center),
+            ZoomAction::Out => self.zoom_view(1.0 / ZOOM_STEP, center),
+            ZoomAction::ToLoop => {
+                if let Some(range) = self.loop_range {
+                    let pad = range.duration() * 0.02;
+                    self.set_view(range.start - pad, range.end + pad);
+                }
+            }
+            ZoomAction::Fit => self.set_view(0.0, duration),
+        }
+    }
+
+    /// Register a tap; after two or more steady taps, replace the tempo map with a
+    /// constant one at the tapped tempo, keeping its downbeat.
+    fn tap_tempo(&mut self, now: f64) {
+        // A pause longer than this starts a fresh tap sequence.
+        const TAP_RESET_SECONDS: f64 = 2.0;
+        const MAX_TAPS: usize = 8;
+
+        if self
+            .tap_times
+            .last()
+            .is_some_and(|last| now - last > TAP_RESET_SECONDS)
+        {
+            self.tap_times.clear();
+        }
+        self.tap_times.push(now);
+        if self.tap_times.len() > MAX_TAPS {
+            self.tap_times.remove(0);
+        }
+        if self.tap_times.len() < 2 {
+            return;
+        }
+        let span = self.tap_times.last().unwrap() - self.tap_times[0];
+        let interval = span / (self.tap_times.len() - 1) as f64;
+        if interval <= 0.0 {
+            return;
+        }
+        let bpm = 60.0 / interval;
+        let duration = self.info.as_ref().map(file_duration_seconds).unwrap_or(0.0);
+        let (downbeat, beats_per_bar) = match &self.beat_grid {
+            Some(grid) => (grid.downbeat_time(), grid.beats_per_bar),
+            None => {
+                let playhead = self
+                    .player
+                    .as_ref()
+                    .map(|p| p.position_seconds())
+                    .unwrap_or(0.0);
+                (playhead, 4)
+            }
+        };
+        self.beat_grid = Some(TempoMap::constant(bpm, downbeat, beats_per_bar, duration));
+        self.sync_metronome();
+    }
+
+    /// Scroll the view to keep the playhead visible according to `follow_mode`.
+    fn follow_playhead(&mut self) {
+        if self.follow_mode == FollowMode::Off || self.follow_paused || self.marker_drag.is_some() {
+            return;
+        }
+        let Some(player) = self.player.as_ref().filter(|p| p.is_playing()) else {
+            return;
+        };
+        let t = player.position_seconds();
+        let span = self.view_x_max - self.view_x_min;
+        match self.follow_mode {
+            FollowMode::Page => {
+                if t < self.view_x_min || t >= self.view_x_max {
+                    // Leave a little lead-in so the playhead isn't glued to the edge.
+                    let start = t - span * 0.05;
+                    self.set_view(start, start + span);
+                }
+            }
+            FollowMode::Continuous => {
+                let start = t - span * 0.5;
+                self.set_view(start, start + span);
+            }
+            FollowMode::Off => {}
+        }
+    }
+
+    /// Scale the visible window by `factor` (>1 zooms in), keeping `anchor` fixed on screen.
+    fn zoom_view(&mut self, factor: f64, anchor: f64) {
+        let Some(info) = self.info.as_ref() else {
+            return;
+        };
+        let duration = file_duration_seconds(info);
+        let min_span = (MIN_VIEW_FRAMES / info.sample_rate as f64).min(duration);
+        let span = self.view_x_max - self.view_x_min;
+        if span <= 0.0 || factor <= 0.0 {
+            return;
+        }
+        let new_span = (span / factor).clamp(min_span, duration.max(min_span));
+        let anchor = anchor.clamp(self.view_x_min, self.view_x_max);
+        let t = (anchor - self.view_x_min) / span;
+        let start = anchor - t * new_span;
+        self.set_view(start, start + new_span);
+    }
+
+    /// Set the visible window, sliding it back inside the file if needed.
+    fn set_view(&mut self, start: f64, end: f64) {
+        let Some(info) = self.info.as_ref() else {
+            return;
+        };
+        let duration = file_duration_seconds(info);
+        let min_span = (MIN_VIEW_FRAMES / info.sample_rate as f64).min(duration);
+        let span = (end - start).clamp(min_span, duration.max(min_span));
+        let start = start.clamp(0.0, (duration - span).max(0.0));
+        self.view_x_min = start;
+        self.view_x_max = start + span;
+    }
+}
+
+impl LoopahApp {
+    /// Transcribe the loop (or the whole file) and save it as a MIDI file.
+    fn export_midi(&self, whole_file: bool) {
+        let (Some(info), Some(track), Some(audio)) =
+            (&self.info, &self.pitch_track, &self.mem_audio)
+        else {
+            return;
+        };
+        let (t0, t1) = match self.loop_range {
+            Some(range) if !whole_file => (range.start, range.end),
+            _ => (0.0, file_duration_seconds(info)),
+        };
+        let stem = self
+            .selected_file
+            .as_ref()
+            .and_then(|p| p.file_stem())
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "notes".to_string());
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("MIDI", &["mid"])
+            .set_file_name(format!("{stem}.mid"))
+            .save_file()
+        else {
+            return;
+        };
+        let notes = transcribe(track, &self.onsets, audio, t0, t1);
+        if let Err(e) = midi::write_notes(&path, &notes, self.beat_grid.as_ref(), t0) {
+            eprintln!("MIDI export failed: {e:#}");
+        }
+    }
+
+    /// Saved loops as ranges and hot cues as point markers.
+    fn export_markers(&self, format: MarkerFormat) {
+        let Some(audio) = &self.selected_file else {
+            return;
+        };
+        let stem = audio
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("markers");
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.label(), &[format.extension()])
+            .set_file_name(format!("{stem}.{}", format.extension()))
+            .save_file()
+        else {
+            return;
+        };
+        let loops = self.library.loops.iter().map(|lp| Marker {
+            start: lp.start,
+            end: lp.end,
+            name: lp.name.clone(),
+        });
+        let cues = self
+            .library
+            .cues
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, t)| {
+                t.map(|t| Marker {
+                    start: t,
+                    end: t,
+                    name: format!("Cue {}", slot + 1),
+                })
+            });
+        let list: Vec<Marker> = loops.chain(cues).collect();
+        if let Err(e) = markers::write(&path, format, &list, audio) {
+            eprintln!("Marker export failed: {e:#}");
+        }
+    }
+
+    /// Ranges become saved loops at the current playback settings; point
+    /// markers fill free hot cue slots.
+    fn import_markers(&mut self, format: MarkerFormat) {
+        let Some(info) = &self.info else {
+            return;
+        };
+        let duration = file_duration_seconds(info);
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.label(), &[format.extension()])
+            .pick_file()
+        else {
+            return;
+        };
+        let list = match markers::read(&path, format, duration) {
+            Ok(list) => list,
+            Err(e) => {
+                eprintln!("Marker import failed: {e:#}");
+                return;
+            }
+        };
+        let active = self.library.active;
+        let mut skipped = 0;
+        for marker in list.into_iter().filter(|m| m.start < duration) {
+            if marker.is_point() {
+                match self.library.cues.iter_mut().find(|c| c.is_none()) {
+                    Some(slot) => *slot = Some(marker.start),
+                    None => skipped += 1,
+                }
+                continue;
+            }
+            let range = LoopRange::ordered(marker.start, marker.end).clamp(duration);
+            self.library.add(
+                range.start,
+                range.end,
+                self.speed,
+                self.pitch_semitones,
+                self.volume,
+            );
+            if let Some(lp) = self.library.loops.last_mut()
+                && !marker.name.is_empty()
+            {
+                lp.name = marker.name;
+            }
+        }
+        // Imported loops are not activated; A/B would overwrite them.
+        self.library.active = active;
+        if skipped > 0 {
+            eprintln!("Marker import: {skipped} markers skipped, all hot cue slots are in use");
+        }
+    }
+
+    fn save_project(&self) {
+        let (Some(audio), Some(info)) = (&self.selected_file, &self.info) else {
+            return;
+        };
+        let stem = audio
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("practice");
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Loopah project", &[project::EXTENSION])
+            .set_file_name(format!("{stem}.{}", project::EXTENSION))
+            .save_file()
+        else {
+            return;
+        };
+        let result = AudioRef::new(&path, audio, info.fingerprint).and_then(|audio| {
+            project::write(
+                &path,
+                &Project {
+                    version: project::VERSION,
+                    audio,
+                    library: self.library.clone(),
+                    loop_range: self.loop_range.map(|r| (r.start, r.end)),
+                    tempo_map: self.beat_grid.clone(),
+                    playback: Playback {
+                        speed: self.speed,
+                        pitch: self.pitch_semitones,
+                        volume: self.volume,
+                        metronome: self.metronome,
+                    },
+                },
+            )
+        });
+        if let Err(e) = result {
+            eprintln!("Project save failed: {e:#}");
+        }
+    }
+
+    /// Open a project and its recording; asks for the recording when it
+    /// cannot be found by path or fingerprint.
+    fn open_project(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Loopah project", &[project::EXTENSION])
+            .pick_file()
+        else {
+            return;
+        };
+        let project = match project::read(&path) {
+            Ok(project) => project,
+            Err(e) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                self.load_error = Some(format!("{name}: {e:#}"));
+                return;
+            }
+        };
+        let audio = project.audio.resolve(&path).or_else(|| {
+            rfd::FileDialog::new()
+                .set_title(format!("Locate {}", project.audio.path))
+                .add_filter("Audio", &["m4a", "aac", "mp3", "wav", "flac"])
+                .pick_file()
+        });
+        if let Some(audio) = audio {
+            self.open_file(audio);
+            self.pending_project = Some(project);
         }
     }
 }
@@ -418,12 +2077,126 @@ impl LoopahApp {
             player.set_loop(secs);
         }
     }
+
+    /// The denoised audio when it is chosen, otherwise the decoded audio.
+    fn selected_audio(&self) -> Option<&Arc<MemoryAudio>> {
+        match &self.denoised {
+            Some(denoised) if self.use_denoised => Some(denoised),
+            _ => self.mem_audio.as_ref(),
+        }
+    }
+
+    /// Point the player at the denoised or the decoded audio.
+    fn sync_source(&self) {
+        if let (Some(player), Some(audio)) = (&self.player, self.selected_audio()) {
+            player.set_source(audio.clone());
+        }
+    }
+
+    /// Route the chosen stems (or the original) to the player.
+    fn sync_stem_mix(&self) {
+        let Some(player) = &self.player else {
+            return;
+        };
+        let gains = match self.source {
+            PlaybackSource::Original => None,
+            PlaybackSource::Harmonic => Some((1.0, 0.0)),
+            PlaybackSource::Percussive => Some((0.0, 1.0)),
+            PlaybackSource::Blend => {
+                let b = self.stem_balance;
+                Some(((2.0 * (1.0 - b)).min(1.0), (2.0 * b).min(1.0)))
+            }
+        };
+        let mix = match (&self.stems, gains) {
+            (Some(stems), Some((harmonic_gain, percussive_gain))) => Some(StemMix {
+                harmonic: stems.harmonic.clone(),
+                percussive: stems.percussive.clone(),
+                harmonic_gain,
+                percussive_gain,
+            }),
+            _ => None,
+        };
+        player.set_stem_mix(mix);
+    }
+
+    /// Hand the beats of the tempo map to the player's metronome, or turn it off.
+    fn sync_metronome(&self) {
+        let Some(player) = &self.player else {
+            return;
+        };
+        let clicks = match (&self.beat_grid, &self.info) {
+            (Some(map), Some(info)) if self.metronome => Some(
+                map.beats_in(0.0, file_duration_seconds(info))
+                    .map(|(i, t)| (t, map.bar_beat(i).1 == 1))
+                    .collect(),
+            ),
+            _ => None,
+        };
+        player.set_metronome(clicks);
+    }
+}
+
+/// Cue slot for a digit key 1–9 pressed this frame, and whether Shift (store)
+/// was held. Physical keys are checked first since Shift+digit yields a symbol
+/// on most layouts.
+fn cue_key(input: &egui::InputState) -> Option<(usize, bool)> {
+    const DIGITS: [egui::Key; CUE_SLOTS] = [
+        egui::Key::Num1,
+        egui::Key::Num2,
+        egui::Key::Num3,
+        egui::Key::Num4,
+        egui::Key::Num5,
+        egui::Key::Num6,
+        egui::Key::Num7,
+        egui::Key::Num8,
+        egui::Key::Num9,
+    ];
+    input.events.iter().find_map(|event| match event {
+        egui::Event::Key {
+            key,
+            physical_key,
+            pressed: true,
+            repeat: false,
+            modifiers,
+        } => {
+            let key = physical_key.unwrap_or(*key);
+            let slot = DIGITS.iter().position(|d| *d == key)?;
+            Some((slot, modifiers.shift))
+        }
+        _ => None,
+    })
 }
 
-fn format_time(secs: f64) -> String {
-    let total_ms = (secs.max(0.0) * 1000.0).round() as i64;
-    let minutes = total_ms / 60_000;
-    let seconds = (total_ms % 60_000) / 1000;
-    let millis = total_ms % 1000;
-    format!("{minutes}:{seconds:02}.{millis:03}")
+/// First onset after `t` (or last before it), ignoring one within a millisecond
+/// so repeated jumps keep moving.
+fn adjacent_onset(onsets: &[f64], t: f64, forward: bool) -> Option<f64> {
+    const EPS: f64 = 0.001;
+    if forward {
+        let i = onsets.partition_point(|o| *o <= t + EPS);
+        onsets.get(i).copied()
+    } else {
+        let i = onsets.partition_point(|o| *o < t - EPS);
+        i.checked_sub(1).map(|i| onsets[i])
+    }
+}
+
+/// "Previous/next onset" buttons for a loop marker at `t`; returns the new time
+/// if one was clicked. `modifier` names the arrow-key shortcut in the tooltip.
+fn onset_buttons(ui: &mut egui::Ui, onsets: &[f64], t: f64, modifier: &str) -> Option<f64> {
+    let mut moved = None;
+    if ui
+        .add_enabled(!onsets.is_empty(), egui::Button::new("◀").small())
+        .on_hover_text(format!("Previous onset ({modifier}+←)"))
+        .clicked()
+    {
+        moved = adjacent_onset(onsets, t, false);
+    }
+    if ui
+        .add_enabled(!onsets.is_empty(), egui::Button::new("▶").small())
+        .on_hover_text(format!("Next onset ({modifier}+→)"))
+        .clicked()
+    {
+        moved = adjacent_onset(onsets, t, true);
+    }
+    moved
 }
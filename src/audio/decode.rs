@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use std::fs::File;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::mpsc;
@@ -13,14 +14,54 @@ use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 
+use super::peaks::{PeakBuilder, PeakPyramid};
+use super::silence::SilenceDetector;
+
 /// Lightweight metadata + preview for an audio file.
 #[derive(Debug, Clone)]
 pub struct DecodedInfo {
     pub sample_rate: u32,
     pub channels: u16,
     pub total_frames: u64,
-    /// Mono RMS preview samples (one value per ~20ms window).
-    pub rms_preview: Vec<f32>,
+    /// Per-channel min/max/RMS peaks, from whole-file down to `BASE_BUCKET_FRAMES`.
+    pub peaks: Arc<PeakPyramid>,
+    /// Mid (channel 0) / side (channel 1) peaks; stereo files only.
+    pub mid_side: Option<Arc<PeakPyramid>>,
+    /// Silent regions in seconds, ascending.
+    pub silences: Vec<(f64, f64)>,
+    /// Hash of the file's bytes; identifies the recording across renames.
+    pub fingerprint: u64,
+}
+
+impl DecodedInfo {
+    /// The file without leading and trailing silence; the whole file when it
+    /// is silent throughout.
+    pub fn audible_range(&self) -> (f64, f64) {
+        let duration = self.total_frames as f64 / self.sample_rate.max(1) as f64;
+        let start = match self.silences.first() {
+            Some(&(begin, end)) if begin <= 0.0 => end,
+            _ => 0.0,
+        };
+        let end = match self.silences.last() {
+            Some(&(begin, end)) if end >= duration => begin,
+            _ => duration,
+        };
+        if end > start {
+            (start, end)
+        } else {
+            (0.0, duration)
+        }
+    }
+
+    /// End of the silence at or after `t`: where sound next starts.
+    pub fn next_audible(&self, t: f64) -> Option<f64> {
+        let duration = self.total_frames as f64 / self.sample_rate.max(1) as f64;
+        self.silences
+            .iter()
+            .find(|(_, end)| *end > t)
+            .map(|(_, end)| *end)
+            .filter(|end| *end < duration)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -32,6 +73,49 @@ pub struct MemoryAudio {
     pub data: Vec<f32>,
 }
 
+impl MemoryAudio {
+    /// Channel sum at `frame`; its sign is what a loop boundary has to match.
+    fn mix_at(&self, frame: usize) -> f32 {
+        let ch = self.channels as usize;
+        self.data[frame * ch..(frame + 1) * ch].iter().sum()
+    }
+
+    /// Frame closest to `frame` (within `radius` frames) where the channel sum
+    /// changes sign, picking the quieter side of the crossing.
+    pub fn nearest_zero_crossing(&self, frame: usize, radius: usize) -> Option<usize> {
+        let frames = self.frames as usize;
+        if self.channels == 0 || frames < 2 {
+            return None;
+        }
+        let crossing = |i: usize| {
+            let (a, b) = (self.mix_at(i - 1), self.mix_at(i));
+            if (a <= 0.0) == (b <= 0.0) {
+                None
+            } else if a.abs() < b.abs() {
+                Some(i - 1)
+            } else {
+                Some(i)
+            }
+        };
+        let frame = frame.clamp(1, frames - 1);
+        for d in 0..=radius {
+            let right = frame + d;
+            if right < frames
+                && let Some(i) = crossing(right)
+            {
+                return Some(i);
+            }
+            if d > 0
+                && frame > d
+                && let Some(i) = crossing(frame - d)
+            {
+                return Some(i);
+            }
+        }
+        None
+    }
+}
+
 /// Events emitted while decoding in the background.
 #[derive(Debug)]
 pub enum LoadEvent {
@@ -67,6 +151,12 @@ fn decode_streaming(
     event_tx: &mpsc::Sender<LoadEvent>,
     pcm_tx: &mpsc::Sender<Arc<Vec<f32>>>,
 ) -> Result<()> {
+    // Hashed in its own pass, since the decoder seeks, and on its own thread
+    // so that playback doesn't wait for it; only `PreviewReady` needs it.
+    let hasher = {
+        let path = path.to_path_buf();
+        thread::spawn(move || fingerprint_file(&path))
+    };
     let file = File::open(path).with_context(|| format!("open {}", path.display()))?;
     let mss = MediaSourceStream::new(Box::new(file), Default::default());
 
@@ -101,17 +191,15 @@ fn decode_streaming(
         .make(&params, &DecoderOptions::default())
         .context("unsupported codec or failed to build decoder")?;
 
-    let window_frames = (sr / 50).max(1) as usize; // ≈20ms
-    let mut rms_preview = Vec::new();
+    let mut peaks = PeakBuilder::new(chs as usize);
+    let mut mid_side = (chs == 2).then(|| PeakBuilder::new(2));
+    let mut ms_scratch = Vec::new();
+    let mut silence = SilenceDetector::new(chs as usize, sr);
     let mut total_frames: u64 = 0;
 
     let mut sample_buf: Option<SampleBuffer<f32>> = None;
     let mut chunk_store: Vec<Arc<Vec<f32>>> = Vec::new();
 
-    // Carry RMS accumulation across packets so there's no dropped tail.
-    let mut acc_sq = 0.0f64;
-    let mut acc_count = 0usize;
-
     while let Ok(packet) = format.next_packet() {
         if packet.track_id() != track_id {
             continue;
@@ -135,26 +223,16 @@ fn decode_streaming(
                 let _ = pcm_tx.send(chunk.clone());
                 chunk_store.push(chunk);
 
-                let chan_count = chs as usize;
-                let frames = samples.len() / chan_count;
-
-                for f in 0..frames {
-                    let base = f * chan_count;
-                    let mut sum = 0.0f32;
-                    for c in 0..chan_count {
-                        sum += samples[base + c];
-                    }
-                    let mono = sum / (chan_count as f32);
-
-                    acc_sq += (mono as f64) * (mono as f64);
-                    acc_count += 1;
-
-                    if acc_count == window_frames {
-                        let rms = (acc_sq / acc_count as f64).sqrt() as f32;
-                        rms_preview.push(rms);
-                        acc_sq = 0.0;
-                        acc_count = 0;
+                // Partial peak buckets carry across packets so there's no dropped tail.
+                peaks.push_interleaved(samples);
+                silence.push_interleaved(samples);
+                if let Some(ms) = mid_side.as_mut() {
+                    ms_scratch.clear();
+                    for lr in samples.chunks_exact(2) {
+                        ms_scratch.push((lr[0] + lr[1]) * 0.5);
+                        ms_scratch.push((lr[0] - lr[1]) * 0.5);
                     }
+                    ms.push_interleaved(&ms_scratch);
                 }
             }
             Err(SymphoniaError::DecodeError(_)) => continue, // skip corrupt packet
@@ -162,11 +240,6 @@ fn decode_streaming(
         }
     }
 
-    if acc_count > 0 {
-        let rms = (acc_sq / acc_count as f64).sqrt() as f32;
-        rms_preview.push(rms);
-    }
-
     // Build contiguous PCM from chunks for future random access features.
     let total_samples: usize = chunk_store.iter().map(|c| c.len()).sum();
     let mut out = Vec::with_capacity(total_samples);
@@ -174,11 +247,20 @@ fn decode_streaming(
         out.extend_from_slice(&chunk);
     }
 
+    let fingerprint = hasher
+        .join()
+        .ok()
+        .context("fingerprint thread panicked")?
+        .with_context(|| format!("read {}", path.display()))?;
+
     let info = DecodedInfo {
         sample_rate: sr,
         channels: chs,
         total_frames,
-        rms_preview,
+        peaks: Arc::new(peaks.finish()),
+        mid_side: mid_side.map(|ms| Arc::new(ms.finish())),
+        silences: silence.finish(),
+        fingerprint,
     };
 
     let audio = MemoryAudio {
@@ -192,3 +274,30 @@ fn decode_streaming(
 
     Ok(())
 }
+
+/// 64-bit FNV-1a of `bytes`: fast, and collisions between recordings are
+/// vanishingly unlikely.
+pub fn fingerprint(bytes: &[u8]) -> u64 {
+    fnv1a(0xcbf2_9ce4_8422_2325, bytes)
+}
+
+/// `fingerprint` of the file at `path`, read in chunks rather than whole.
+pub fn fingerprint_file(path: &Path) -> std::io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut hash = fingerprint(&[]);
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => return Ok(hash),
+            Ok(n) => hash = fnv1a(hash, &buf[..n]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
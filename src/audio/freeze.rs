@@ -0,0 +1,146 @@
+use symphonia::core::dsp::complex::Complex;
+use symphonia::core::dsp::fft::Fft;
+
+/// Capture window: long enough to resolve the partials of a chord.
+const FFT_SIZE: usize = 4096;
+/// 75% overlap keeps the resynthesized drone smooth.
+const HOP: usize = FFT_SIZE / 4;
+
+/// Endless drone built from one captured spectrum: every hop reuses the frozen
+/// magnitudes with fresh random phases and overlap-adds the result.
+pub struct FreezeVoice {
+    fft: Fft,
+    window: Vec<f32>,
+    /// Captured magnitudes per channel, DC through Nyquist.
+    captured: Vec<Vec<f32>>,
+    /// `captured` after frequency scaling for pitch shift and rate conversion.
+    shifted: Vec<Vec<f32>>,
+    /// Source-to-output sample rate ratio that the scaling compensates for.
+    rate_ratio: f64,
+    buf: Vec<Complex>,
+    /// Overlap-add accumulator per channel, `FFT_SIZE` long.
+    accum: Vec<Vec<f32>>,
+    /// Samples of the current hop already handed out.
+    read: usize,
+    rng: u32,
+}
+
+impl FreezeVoice {
+    /// Capture the spectrum around `frame` of a source with `frames` frames,
+    /// read through `sample` (an interleaved sample index). `rate_ratio` is
+    /// source rate / output rate.
+    pub fn capture(
+        sample: impl Fn(usize) -> f32,
+        channels: usize,
+        frames: usize,
+        frame: usize,
+        rate_ratio: f64,
+        semitones: f32,
+    ) -> Self {
+        let fft = Fft::new(FFT_SIZE);
+        let window: Vec<f32> = (0..FFT_SIZE)
+            .map(|i| {
+                let phase = i as f32 / FFT_SIZE as f32;
+                0.5 - 0.5 * (std::f32::consts::TAU * phase).cos()
+            })
+            .collect();
+        let start = frame as isize - (FFT_SIZE / 2) as isize;
+        let mut buf = vec![Complex::default(); FFT_SIZE];
+        let captured: Vec<Vec<f32>> = (0..channels)
+            .map(|c| {
+                for (i, b) in buf.iter_mut().enumerate() {
+                    let idx = start + i as isize;
+                    let s = if idx >= 0 && (idx as usize) < frames {
+                        sample(idx as usize * channels + c)
+                    } else {
+                        0.0
+                    };
+                    *b = Complex::new(s * window[i], 0.0);
+                }
+                fft.fft_inplace(&mut buf);
+                buf[..=FFT_SIZE / 2]
+                    .iter()
+                    .map(|c| (c.re * c.re + c.im * c.im).sqrt())
+                    .collect()
+            })
+            .collect();
+
+        let mut voice = Self {
+            fft,
+            window,
+            shifted: captured.clone(),
+            captured,
+            rate_ratio,
+            buf,
+            accum: vec![vec![0.0; FFT_SIZE]; channels],
+            read: HOP,
+            rng: 0x9E37_79B9,
+        };
+        voice.set_semitones(semitones);
+        voice
+    }
+
+    /// Retune the drone; partials move by `semitones` from the captured pitch.
+    /// Rewrites `shifted` in place, so it is safe on the audio thread.
+    pub fn set_semitones(&mut self, semitones: f32) {
+        let factor = 2f64.powf(semitones as f64 / 12.0) * self.rate_ratio;
+        for (shifted, mags) in self.shifted.iter_mut().zip(&self.captured) {
+            for (b, out) in shifted.iter_mut().enumerate() {
+                let src = b as f64 / factor;
+                let i = src.floor() as usize;
+                *out = if i + 1 >= mags.len() {
+                    0.0
+                } else {
+                    let frac = (src - i as f64) as f32;
+                    mags[i] + (mags[i + 1] - mags[i]) * frac
+                };
+            }
+        }
+    }
+
+    /// Fill interleaved `output` with the drone at `volume`.
+    pub fn render(&mut self, volume: f32, output: &mut [f32]) {
+        let ch = self.accum.len();
+        if ch == 0 {
+            output.fill(0.0);
+            return;
+        }
+        for frame in output.chunks_exact_mut(ch) {
+            if self.read >= HOP {
+                self.next_hop();
+            }
+            for (c, out) in frame.iter_mut().enumerate() {
+                *out = self.accum[c][self.read] * volume;
+            }
+            self.read += 1;
+        }
+    }
+
+    /// Shift the accumulator by one hop and overlap-add a new random-phase frame.
+    fn next_hop(&mut self) {
+        for c in 0..self.accum.len() {
+            let acc = &mut self.accum[c];
+            acc.copy_within(HOP.., 0);
+            acc[FFT_SIZE - HOP..].fill(0.0);
+
+            let mags = &self.shifted[c];
+            for (b, m) in mags.iter().enumerate() {
+                // xorshift: cheap, allocation-free noise for the phases.
+                self.rng ^= self.rng << 13;
+                self.rng ^= self.rng >> 17;
+                self.rng ^= self.rng << 5;
+                let phase = self.rng as f32 / u32::MAX as f32 * std::f32::consts::TAU;
+                let (sin, cos) = phase.sin_cos();
+                self.buf[b] = Complex::new(m * cos, m * sin);
+                if b > 0 && b < FFT_SIZE / 2 {
+                    self.buf[FFT_SIZE - b] = Complex::new(m * cos, -m * sin);
+                }
+            }
+            self.fft.ifft_inplace(&mut self.buf);
+            for ((a, s), w) in acc.iter_mut().zip(&self.buf).zip(&self.window) {
+                *a += s.re * w;
+            }
+        }
+        self.read = 0;
+    }
+}
@@ -1,2 +1,6 @@
 pub mod decode;
+pub mod freeze;
+pub mod peaks;
+pub mod pitch;
 pub mod playback;
+pub mod silence;
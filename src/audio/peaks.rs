@@ -0,0 +1,177 @@
+/// Frames summarised by one bucket of the finest pyramid level. Anything finer
+/// than this is read straight from `MemoryAudio`.
+pub const BASE_BUCKET_FRAMES: usize = 64;
+/// Each coarser level merges this many buckets of the level below.
+const LEVEL_FACTOR: usize = 4;
+
+/// Min/max/RMS summary of one bucket of samples.
+#[derive(Debug, Clone, Copy)]
+pub struct Peak {
+    pub min: f32,
+    pub max: f32,
+    pub rms: f32,
+}
+
+impl Peak {
+    pub const SILENT: Peak = Peak {
+        min: 0.0,
+        max: 0.0,
+        rms: 0.0,
+    };
+
+    /// Combine equally weighted buckets into one.
+    pub fn merge(items: &[Peak]) -> Peak {
+        if items.is_empty() {
+            return Peak::SILENT;
+        }
+        let mut min = f32::INFINITY;
+        let mut max = f32::NEG_INFINITY;
+        let mut sq = 0.0f64;
+        for p in items {
+            min = min.min(p.min);
+            max = max.max(p.max);
+            sq += (p.rms as f64) * (p.rms as f64);
+        }
+        Peak {
+            min,
+            max,
+            rms: (sq / items.len() as f64).sqrt() as f32,
+        }
+    }
+}
+
+/// One resolution of the pyramid: `bucket_frames` frames per bucket, per channel.
+#[derive(Debug, Clone)]
+pub struct PeakLevel {
+    pub bucket_frames: usize,
+    /// One bucket list per channel, all the same length.
+    pub channels: Vec<Vec<Peak>>,
+}
+
+impl PeakLevel {
+    pub fn len(&self) -> usize {
+        self.channels.first().map(|c| c.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bucket `index` summarised across all channels.
+    pub fn mono(&self, index: usize) -> Peak {
+        if self.channels.is_empty() {
+            return Peak::SILENT;
+        }
+        let mut min = f32::INFINITY;
+        let mut max = f32::NEG_INFINITY;
+        let mut sq = 0.0f64;
+        for ch in &self.channels {
+            let p = ch[index];
+            min = min.min(p.min);
+            max = max.max(p.max);
+            sq += (p.rms as f64) * (p.rms as f64);
+        }
+        Peak {
+            min,
+            max,
+            rms: (sq / self.channels.len() as f64).sqrt() as f32,
+        }
+    }
+}
+
+/// Mipmapped min/max/RMS peaks per channel, finest level first.
+#[derive(Debug, Clone, Default)]
+pub struct PeakPyramid {
+    pub levels: Vec<PeakLevel>,
+}
+
+impl PeakPyramid {
+    /// Coarsest level whose buckets are no wider than `frames_per_point`.
+    /// `None` means the caller should read raw samples instead.
+    pub fn level_for(&self, frames_per_point: f64) -> Option<&PeakLevel> {
+        self.levels
+            .iter()
+            .rev()
+            .find(|l| l.bucket_frames as f64 <= frames_per_point)
+    }
+}
+
+/// Incrementally builds a `PeakPyramid` from interleaved PCM as it is decoded.
+pub struct PeakBuilder {
+    channels: usize,
+    acc_min: Vec<f32>,
+    acc_max: Vec<f32>,
+    acc_sq: Vec<f64>,
+    acc_count: usize,
+    base: Vec<Vec<Peak>>,
+}
+
+impl PeakBuilder {
+    pub fn new(channels: usize) -> Self {
+        Self {
+            channels,
+            acc_min: vec![f32::INFINITY; channels],
+            acc_max: vec![f32::NEG_INFINITY; channels],
+            acc_sq: vec![0.0; channels],
+            acc_count: 0,
+            base: vec![Vec::new(); channels],
+        }
+    }
+
+    /// Feed interleaved samples; partial buckets carry over to the next call.
+    pub fn push_interleaved(&mut self, samples: &[f32]) {
+        if self.channels == 0 {
+            return;
+        }
+        for frame in samples.chunks_exact(self.channels) {
+            for (c, &s) in frame.iter().enumerate() {
+                self.acc_min[c] = self.acc_min[c].min(s);
+                self.acc_max[c] = self.acc_max[c].max(s);
+                self.acc_sq[c] += (s as f64) * (s as f64);
+            }
+            self.acc_count += 1;
+            if self.acc_count == BASE_BUCKET_FRAMES {
+                self.flush_bucket();
+            }
+        }
+    }
+
+    fn flush_bucket(&mut self) {
+        for c in 0..self.channels {
+            self.base[c].push(Peak {
+                min: self.acc_min[c],
+                max: self.acc_max[c],
+                rms: (self.acc_sq[c] / self.acc_count as f64).sqrt() as f32,
+            });
+            self.acc_min[c] = f32::INFINITY;
+            self.acc_max[c] = f32::NEG_INFINITY;
+            self.acc_sq[c] = 0.0;
+        }
+        self.acc_count = 0;
+    }
+
+    /// Flush the tail bucket and build the coarser levels up to a single bucket.
+    pub fn finish(mut self) -> PeakPyramid {
+        if self.acc_count > 0 {
+            self.flush_bucket();
+        }
+        let mut levels = vec![PeakLevel {
+            bucket_frames: BASE_BUCKET_FRAMES,
+            channels: self.base,
+        }];
+        while levels.last().map(|l| l.len() > 1).unwrap_or(false) {
+            let prev = levels.last().unwrap();
+            let channels = prev
+                .channels
+                .iter()
+                .map(|ch| ch.chunks(LEVEL_FACTOR).map(Peak::merge).collect())
+                .collect();
+            let bucket_frames = prev.bucket_frames * LEVEL_FACTOR;
+            levels.push(PeakLevel {
+                bucket_frames,
+                channels,
+            });
+        }
+        PeakPyramid { levels }
+    }
+}
@@ -0,0 +1,108 @@
+use ssstretch::Stretch;
+
+/// Output frames handed to the stretcher per call; longer device blocks are split.
+const MAX_BLOCK: usize = 4096;
+/// Fastest playback rate, which bounds the input read per output block.
+pub const MAX_SPEED: f64 = 4.0;
+
+/// Time stretch and pitch shift for the player's output blocks: any number of
+/// input frames becomes one block of output, with the pitch transposed
+/// independently of the rate.
+///
+/// Runs inside the audio callback, so `process` never allocates: the
+/// per-channel buffers and pointer arrays are sized once in `new`.
+pub struct Stretcher {
+    stretch: Stretch,
+    channels: usize,
+    input: Vec<Vec<f32>>,
+    output: Vec<Vec<f32>>,
+    input_ptrs: Vec<*const f32>,
+    output_ptrs: Vec<*mut f32>,
+    /// One input frame, filled by the caller.
+    frame: Vec<f32>,
+}
+
+// SAFETY: the stretcher owns its C++ state exclusively and is only touched by
+// whichever thread holds the player's state mutex. The pointer arrays are
+// scratch space, refreshed from `input`/`output` before every use.
+unsafe impl Send for Stretcher {}
+
+impl Stretcher {
+    /// Allocates; build it off the audio thread.
+    pub fn new(channels: usize, sample_rate: f32, semitones: f32) -> Self {
+        let mut stretch = Stretch::new();
+        stretch.preset_default(channels as i32, sample_rate);
+        stretch.set_transpose_semitones(semitones, None);
+        let max_input = (MAX_BLOCK as f64 * MAX_SPEED).ceil() as usize + 1;
+        Self {
+            stretch,
+            channels,
+            input: vec![vec![0.0; max_input]; channels],
+            output: vec![vec![0.0; MAX_BLOCK]; channels],
+            input_ptrs: vec![std::ptr::null(); channels],
+            output_ptrs: vec![std::ptr::null_mut(); channels],
+            frame: vec![0.0; channels],
+        }
+    }
+
+    pub fn set_semitones(&mut self, semitones: f32) {
+        self.stretch.set_transpose_semitones(semitones, None);
+    }
+
+    /// Drop buffered audio, e.g. after a seek.
+    pub fn reset(&mut self) {
+        self.stretch.reset();
+    }
+
+    /// Fill the interleaved `output` from `frames_in` input frames, which
+    /// `next_frame` writes one at a time; `frames_in` may be at most
+    /// `MAX_SPEED` times the output length.
+    pub fn process(
+        &mut self,
+        frames_in: usize,
+        output: &mut [f32],
+        mut next_frame: impl FnMut(&mut [f32]),
+    ) {
+        let ch = self.channels;
+        let frames_out = output.len() / ch.max(1);
+        if frames_out == 0 {
+            return;
+        }
+        let mut read = 0;
+        for (k, block) in output.chunks_mut(MAX_BLOCK * ch).enumerate() {
+            let out_len = block.len() / ch;
+            // Input is split across blocks in proportion to their length.
+            let in_end = frames_in * (k * MAX_BLOCK + out_len) / frames_out;
+            let in_len = in_end - read;
+            read = in_end;
+            for f in 0..in_len {
+                next_frame(&mut self.frame);
+                for (lane, s) in self.input.iter_mut().zip(&self.frame) {
+                    lane[f] = *s;
+                }
+            }
+            for (ptr, lane) in self.input_ptrs.iter_mut().zip(&self.input) {
+                *ptr = lane.as_ptr();
+            }
+            for (ptr, lane) in self.output_ptrs.iter_mut().zip(&mut self.output) {
+                *ptr = lane.as_mut_ptr();
+            }
+            // SAFETY: one pointer per channel, each to at least `in_len` input
+            // and `out_len` output samples owned by `self` and not otherwise
+            // borrowed during the call.
+            unsafe {
+                self.stretch.process(
+                    &self.input_ptrs,
+                    in_len as i32,
+                    &mut self.output_ptrs,
+                    out_len as i32,
+                );
+            }
+            for (f, frame) in block.chunks_exact_mut(ch).enumerate() {
+                for (s, lane) in frame.iter_mut().zip(&self.output) {
+                    *s = lane[f];
+                }
+            }
+        }
+    }
+}
@@ -6,6 +6,8 @@ use anyhow::{Context, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 
 use super::decode::MemoryAudio;
+use super::freeze::FreezeVoice;
+use super::pitch::{MAX_SPEED, Stretcher};
 
 /// Player that can either stream progressively decoded chunks or play a full buffer.
 pub struct Player {
@@ -29,8 +31,65 @@ struct MemoryState {
     pos_frame: f64,
     ratio: f64,
     loop_range: Option<(f64, f64)>,
+    scrub: Option<ScrubState>,
+    metronome: Option<Metronome>,
+    /// Playback rate; 1 is original tempo. Pitch is kept by `stretcher`.
+    speed: f64,
+    /// Present while a non-zero pitch shift or a speed change is set.
+    stretcher: Option<Stretcher>,
+    semitones: f32,
+    /// Fraction of a source frame owed to the stretcher from earlier blocks.
+    stretch_carry: f64,
+    /// Drone replacing playback while a frame is frozen.
+    freeze: Option<Box<FreezeVoice>>,
+    /// Replaces `src` as the sample source while set.
+    stems: Option<StemMix>,
 }
 
+/// Two stems of the source played together with their own gains.
+#[derive(Clone)]
+pub struct StemMix {
+    pub harmonic: Arc<MemoryAudio>,
+    pub percussive: Arc<MemoryAudio>,
+    pub harmonic_gain: f32,
+    pub percussive_gain: f32,
+}
+
+impl StemMix {
+    fn sample(&self, i: usize) -> f32 {
+        self.harmonic.data[i] * self.harmonic_gain + self.percussive.data[i] * self.percussive_gain
+    }
+}
+
+/// Clicks mixed into memory playback at given source positions.
+struct Metronome {
+    /// Click positions in source frames, ascending, with an accent flag.
+    clicks: Vec<(f64, bool)>,
+    /// Currently sounding click: output frames elapsed and accent flag.
+    voice: Option<(f64, bool)>,
+}
+
+/// Click length and pitches (accent for bar starts).
+const CLICK_SECONDS: f64 = 0.04;
+const CLICK_HZ: f64 = 1000.0;
+const ACCENT_HZ: f64 = 1500.0;
+
+/// Short windowed grains repeated around a pointer position while scrubbing.
+struct ScrubState {
+    center_frame: f64,
+    grain_start: f64,
+    grain_pos: f64,
+    grain_frames: f64,
+}
+
+/// Smaller shifts and speed changes are inaudible; the stretcher is dropped
+/// instead.
+const MIN_SHIFT_SEMITONES: f32 = 0.01;
+const MIN_SPEED_CHANGE: f64 = 0.001;
+
+/// Length of one scrub grain (≈60ms); short enough to feel immediate.
+const SCRUB_GRAIN_SECONDS: f64 = 0.06;
+
 struct StreamState {
     receiver: Receiver<Arc<Vec<f32>>>,
     pending: VecDeque<Arc<Vec<f32>>>,
@@ -58,7 +117,7 @@ impl Player {
         }
     }
 
-    pub fn from_memory(src: MemoryAudio) -> Result<Self> {
+    pub fn from_memory(src: Arc<MemoryAudio>) -> Result<Self> {
         let host = cpal::default_host();
         let device = host.default_output_device().context("no output device")?;
         let mut config = device.default_output_config()?.config();
@@ -68,10 +127,18 @@ impl Player {
 
         let state = State {
             mode: PlaybackMode::Memory(MemoryState {
-                src: Arc::new(src),
+                src,
                 pos_frame: 0.0,
                 ratio,
                 loop_range: None,
+                scrub: None,
+                metronome: None,
+                speed: 1.0,
+                stretcher: None,
+                semitones: 0.0,
+                stretch_carry: 0.0,
+                freeze: None,
+                stems: None,
             }),
             playing: true,
             volume: 1.0,
@@ -131,6 +198,13 @@ impl Player {
                     let playing = st.playing;
                     let volume = st.volume;
                     match &mut st.mode {
+                        PlaybackMode::Memory(MemoryState {
+                            freeze: Some(voice),
+                            ..
+                        }) => voice.render(volume, output),
+                        PlaybackMode::Memory(mem) if mem.scrub.is_some() => {
+                            process_scrub(mem, volume, output)
+                        }
                         PlaybackMode::Memory(mem) => process_memory(mem, playing, volume, output),
                         PlaybackMode::Stream(stream) => {
                             process_stream(stream, playing, volume, output)
@@ -187,56 +261,266 @@ impl Player {
     }
 
     pub fn set_loop(&self, loop_range_secs: Option<(f64, f64)>) {
-        if let Ok(mut st) = self.shared.lock() {
-            if let PlaybackMode::Memory(mem) = &mut st.mode {
-                mem.set_loop(loop_range_secs);
-            }
+        if let Ok(mut st) = self.shared.lock()
+            && let PlaybackMode::Memory(mem) = &mut st.mode
+        {
+            mem.set_loop(loop_range_secs);
         }
     }
 
     pub fn set_position_seconds(&self, seconds: f64) {
+        if let Ok(mut st) = self.shared.lock()
+            && let PlaybackMode::Memory(mem) = &mut st.mode
+        {
+            mem.set_position_seconds(seconds);
+        }
+    }
+
+    /// Swap the memory source for processed audio of the same layout (e.g. a
+    /// denoised copy), keeping position, loop and effects.
+    pub fn set_source(&self, src: Arc<MemoryAudio>) {
+        if let Ok(mut st) = self.shared.lock()
+            && let PlaybackMode::Memory(mem) = &mut st.mode
+            && src.channels == mem.src.channels
+            && src.frames == mem.src.frames
+        {
+            mem.src = src;
+        }
+    }
+
+    /// Play a mix of separated stems instead of the original (`None` restores it).
+    /// Stems must have the same layout as the source.
+    pub fn set_stem_mix(&self, mix: Option<StemMix>) {
+        if let Ok(mut st) = self.shared.lock()
+            && let PlaybackMode::Memory(mem) = &mut st.mode
+        {
+            mem.stems = mix;
+        }
+    }
+
+    /// Transpose memory playback by `semitones` without changing tempo.
+    pub fn set_pitch_semitones(&self, semitones: f32) {
+        self.update_stretcher(|mem| {
+            mem.semitones = semitones;
+            if let Some(voice) = mem.freeze.as_mut() {
+                voice.set_semitones(semitones);
+            }
+        });
+    }
+
+    /// Change tempo without changing pitch; 1.0 is the original speed.
+    pub fn set_speed(&self, speed: f64) {
+        self.update_stretcher(|mem| mem.speed = speed.clamp(0.1, MAX_SPEED));
+    }
+
+    /// Apply `change` to memory playback, then bring the stretcher in line.
+    /// Building or dropping a stretcher allocates or frees its C++ state, so
+    /// that happens outside the lock the audio callback takes.
+    fn update_stretcher(&self, change: impl FnOnce(&mut MemoryState)) {
+        let (channels, rate, semitones, needed) = {
+            let Ok(mut st) = self.shared.lock() else {
+                return;
+            };
+            let PlaybackMode::Memory(mem) = &mut st.mode else {
+                return;
+            };
+            change(mem);
+            let needed = mem.needs_stretcher();
+            match mem.stretcher.as_mut() {
+                Some(stretcher) if needed => {
+                    stretcher.set_semitones(mem.semitones);
+                    return;
+                }
+                None if !needed => return,
+                _ => {}
+            }
+            let rate = mem.src.sample_rate as f64 / mem.ratio;
+            (
+                mem.src.channels as usize,
+                rate as f32,
+                mem.semitones,
+                needed,
+            )
+        };
+        let fresh = needed.then(|| Stretcher::new(channels, rate, semitones));
+        let old = {
+            let Ok(mut st) = self.shared.lock() else {
+                return;
+            };
+            let PlaybackMode::Memory(mem) = &mut st.mode else {
+                return;
+            };
+            let mut fresh = fresh;
+            // Another change may have landed while the stretcher was built.
+            if let Some(stretcher) = fresh.as_mut() {
+                stretcher.set_semitones(mem.semitones);
+            }
+            mem.stretch_carry = 0.0;
+            std::mem::replace(&mut mem.stretcher, fresh)
+        };
+        drop(old);
+    }
+
+    pub fn set_volume(&self, volume: f32) {
         if let Ok(mut st) = self.shared.lock() {
-            if let PlaybackMode::Memory(mem) = &mut st.mode {
-                mem.set_position_seconds(seconds);
+            st.volume = volume.max(0.0);
+        }
+    }
+
+    /// Click on each `(seconds, accent)` entry during playback; `None` turns the
+    /// metronome off.
+    pub fn set_metronome(&self, clicks: Option<Vec<(f64, bool)>>) {
+        if let Ok(mut st) = self.shared.lock()
+            && let PlaybackMode::Memory(mem) = &mut st.mode
+        {
+            let sr = mem.src.sample_rate as f64;
+            mem.metronome = clicks.map(|clicks| Metronome {
+                clicks: clicks.into_iter().map(|(t, a)| (t * sr, a)).collect(),
+                voice: None,
+            });
+        }
+    }
+
+    /// Hold the spectrum at the playhead as an endless drone (it follows the
+    /// pitch shift and sounds even while paused); `false` releases it.
+    /// Returns whether memory playback took the change; streamed playback
+    /// has nothing to freeze. The capture allocates and runs FFTs, so like
+    /// `update_stretcher` it happens outside the lock the audio callback takes.
+    pub fn set_freeze(&self, on: bool) -> bool {
+        let capture = {
+            let Ok(st) = self.shared.lock() else {
+                return false;
+            };
+            let PlaybackMode::Memory(mem) = &st.mode else {
+                return false;
+            };
+            on.then(|| {
+                (
+                    Arc::clone(&mem.src),
+                    mem.stems.clone(),
+                    mem.pos_frame as usize,
+                    mem.ratio,
+                    mem.semitones,
+                )
+            })
+        };
+        let fresh = capture.map(|(src, stems, frame, ratio, semitones)| {
+            let voice = Box::new(FreezeVoice::capture(
+                |i| match &stems {
+                    Some(mix) => mix.sample(i),
+                    None => src.data[i],
+                },
+                src.channels as usize,
+                src.frames as usize,
+                frame,
+                ratio,
+                semitones,
+            ));
+            (voice, semitones)
+        });
+        let old = {
+            let Ok(mut st) = self.shared.lock() else {
+                return false;
+            };
+            let PlaybackMode::Memory(mem) = &mut st.mode else {
+                return false;
+            };
+            let mut fresh = fresh;
+            // The pitch may have changed while the voice was captured.
+            if let Some((voice, semitones)) = fresh.as_mut()
+                && *semitones != mem.semitones
+            {
+                voice.set_semitones(mem.semitones);
             }
+            let fresh = fresh.map(|(voice, _)| voice);
+            std::mem::replace(&mut mem.freeze, fresh)
+        };
+        drop(old);
+        true
+    }
+
+    /// Audibly scrub around `seconds` (grains are played even while paused).
+    /// Pass `None` to stop scrubbing and resume normal playback.
+    pub fn set_scrub(&self, seconds: Option<f64>) {
+        if let Ok(mut st) = self.shared.lock()
+            && let PlaybackMode::Memory(mem) = &mut st.mode
+        {
+            mem.set_scrub(seconds);
         }
     }
 }
 
 fn process_memory(mem: &mut MemoryState, playing: bool, volume: f32, output: &mut [f32]) {
+    let ch = mem.src.channels as usize;
+    if !playing || ch == 0 {
+        output.fill(0.0);
+        return;
+    }
+
+    match mem.stretcher.take() {
+        Some(mut stretcher) => {
+            // `speed` times as many source frames go into each block; the
+            // stretcher fits them in without changing their pitch.
+            let out_frames = output.len() / ch;
+            mem.stretch_carry += out_frames as f64 * mem.speed;
+            let frames_in = mem.stretch_carry.floor();
+            mem.stretch_carry -= frames_in;
+            stretcher.process(frames_in as usize, output, |frame| mem.next_frame(frame));
+            mem.stretcher = Some(stretcher);
+        }
+        None => {
+            for frame in output.chunks_exact_mut(ch) {
+                mem.next_frame(frame);
+            }
+        }
+    }
+    for s in output.iter_mut() {
+        *s *= volume;
+    }
+    let total_frames = mem.src.frames as f64;
+    if mem.pos_frame >= total_frames {
+        mem.pos_frame = total_frames;
+    }
+}
+
+fn process_scrub(mem: &mut MemoryState, volume: f32, output: &mut [f32]) {
     let src = Arc::clone(&mem.src);
     let ch = src.channels as usize;
-    if !playing || ch == 0 {
+    let Some(scrub) = mem.scrub.as_mut() else {
+        output.fill(0.0);
+        return;
+    };
+    if ch == 0 {
         output.fill(0.0);
         return;
     }
 
     let total_frames = src.frames as usize;
     let out_frames = output.len() / ch;
-    let mut wrote = 0usize;
     for f in 0..out_frames {
-        mem.enforce_loop_bounds();
-        let p = mem.pos_frame;
-        let i0 = p.floor() as usize;
-        if i0 >= total_frames.saturating_sub(1) {
-            break;
+        if scrub.grain_pos >= scrub.grain_frames {
+            // Start the next grain around the latest pointer position.
+            scrub.grain_start = (scrub.center_frame - scrub.grain_frames * 0.5).max(0.0);
+            scrub.grain_pos = 0.0;
         }
-        let frac = (p - i0 as f64) as f32;
-        let i1 = i0 + 1;
-        for c in 0..ch {
-            let s0 = src.data[i0 * ch + c];
-            let s1 = src.data[i1 * ch + c];
-            output[f * ch + c] = (s0 + (s1 - s0) * frac) * volume;
+        let p = scrub.grain_start + scrub.grain_pos;
+        let i0 = p.floor() as usize;
+        if i0 + 1 >= total_frames {
+            for c in 0..ch {
+                output[f * ch + c] = 0.0;
+            }
+        } else {
+            // Hann window keeps grain boundaries click-free.
+            let phase = scrub.grain_pos / scrub.grain_frames;
+            let window = (0.5 - 0.5 * (std::f64::consts::TAU * phase).cos()) as f32;
+            let frac = (p - i0 as f64) as f32;
+            for c in 0..ch {
+                let s0 = src.data[i0 * ch + c];
+                let s1 = src.data[(i0 + 1) * ch + c];
+                output[f * ch + c] = (s0 + (s1 - s0) * frac) * window * volume;
+            }
         }
-        mem.pos_frame += mem.ratio;
-        mem.enforce_loop_bounds();
-        wrote += 1;
-    }
-    for s in &mut output[wrote * ch..] {
-        *s = 0.0;
-    }
-    if mem.pos_frame >= total_frames as f64 {
-        mem.pos_frame = total_frames as f64;
+        scrub.grain_pos += mem.ratio;
     }
 }
 
@@ -349,7 +633,71 @@ fn zero_from(buf: &mut [f32], start: usize) {
     }
 }
 
+impl Metronome {
+    /// Click sample for the output frame that reads source frames `[pos, pos + step)`.
+    fn next_sample(&mut self, pos: f64, step: f64, out_rate: f64) -> f32 {
+        let k = self.clicks.partition_point(|(c, _)| *c < pos);
+        if let Some(&(c, accent)) = self.clicks.get(k)
+            && c < pos + step
+        {
+            self.voice = Some((0.0, accent));
+        }
+        let Some((elapsed, accent)) = self.voice.as_mut() else {
+            return 0.0;
+        };
+        let t = *elapsed / out_rate;
+        if t >= CLICK_SECONDS {
+            self.voice = None;
+            return 0.0;
+        }
+        *elapsed += 1.0;
+        let hz = if *accent { ACCENT_HZ } else { CLICK_HZ };
+        let env = (-t / (CLICK_SECONDS * 0.2)).exp();
+        (0.5 * env * (std::f64::consts::TAU * hz * t).sin()) as f32
+    }
+}
+
 impl MemoryState {
+    fn needs_stretcher(&self) -> bool {
+        self.semitones.abs() >= MIN_SHIFT_SEMITONES || (self.speed - 1.0).abs() > MIN_SPEED_CHANGE
+    }
+
+    /// Write the source frame at the playhead, converted to the device rate
+    /// and with any metronome click, then advance the playhead by one device
+    /// frame. Past the end of the source the frame is silent.
+    fn next_frame(&mut self, frame: &mut [f32]) {
+        self.enforce_loop_bounds();
+        let ch = frame.len();
+        let p = self.pos_frame;
+        let i0 = p.floor() as usize;
+        if i0 + 1 >= self.src.frames as usize {
+            frame.fill(0.0);
+            return;
+        }
+        let frac = (p - i0 as f64) as f32;
+        let out_rate = self.src.sample_rate as f64 / self.ratio;
+        let click = self
+            .metronome
+            .as_mut()
+            .map(|m| m.next_sample(p, self.ratio, out_rate))
+            .unwrap_or(0.0);
+        for (c, out) in frame.iter_mut().enumerate() {
+            let s0 = self.sample(i0 * ch + c);
+            let s1 = self.sample((i0 + 1) * ch + c);
+            *out = s0 + (s1 - s0) * frac + click;
+        }
+        self.pos_frame += self.ratio;
+        self.enforce_loop_bounds();
+    }
+
+    /// Interleaved sample `i` of the stem mix, or of the source without one.
+    fn sample(&self, i: usize) -> f32 {
+        match &self.stems {
+            Some(mix) => mix.sample(i),
+            None => self.src.data[i],
+        }
+    }
+
     fn set_loop(&mut self, range_secs: Option<(f64, f64)>) {
         if let Some((start, end)) = range_secs {
             let sr = self.src.sample_rate as f64;
@@ -378,6 +726,32 @@ impl MemoryState {
         let frame = (seconds * sr).clamp(0.0, (self.src.frames as f64 - 1.0).max(0.0));
         self.pos_frame = frame;
         self.enforce_loop_bounds();
+        if let Some(stretcher) = self.stretcher.as_mut() {
+            stretcher.reset();
+        }
+    }
+
+    fn set_scrub(&mut self, seconds: Option<f64>) {
+        match seconds {
+            Some(sec) => {
+                let sr = self.src.sample_rate as f64;
+                let center = (sec * sr).clamp(0.0, (self.src.frames as f64 - 1.0).max(0.0));
+                match &mut self.scrub {
+                    Some(scrub) => scrub.center_frame = center,
+                    None => {
+                        let grain_frames = (SCRUB_GRAIN_SECONDS * sr).max(2.0);
+                        self.scrub = Some(ScrubState {
+                            center_frame: center,
+                            grain_start: 0.0,
+                            // Force a fresh grain on the next callback.
+                            grain_pos: grain_frames,
+                            grain_frames,
+                        });
+                    }
+                }
+            }
+            None => self.scrub = None,
+        }
     }
 
     fn enforce_loop_bounds(&mut self) {
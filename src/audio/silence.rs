@@ -0,0 +1,81 @@
+/// Level below which a block counts as silent (about −48 dBFS RMS), low
+/// enough that quiet playing is kept but tape hiss and room tone are not.
+const SILENCE_RMS: f64 = 0.004;
+/// RMS is measured over blocks of this length.
+const BLOCK_SECONDS: f64 = 0.05;
+/// Shorter quiet stretches are pauses in the music, not silence.
+const MIN_SILENCE_SECONDS: f64 = 0.5;
+
+/// Finds silent regions incrementally while a file is decoded.
+pub struct SilenceDetector {
+    channels: usize,
+    sample_rate: f64,
+    block_frames: usize,
+    /// Sum of squares and frames in the current block.
+    sum_sq: f64,
+    filled: usize,
+    /// Frames seen in completed blocks.
+    frame: u64,
+    /// First frame of the silent run in progress.
+    run_start: Option<u64>,
+    regions: Vec<(f64, f64)>,
+}
+
+impl SilenceDetector {
+    pub fn new(channels: usize, sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as f64;
+        Self {
+            channels: channels.max(1),
+            sample_rate,
+            block_frames: ((BLOCK_SECONDS * sample_rate) as usize).max(1),
+            sum_sq: 0.0,
+            filled: 0,
+            frame: 0,
+            run_start: None,
+            regions: Vec::new(),
+        }
+    }
+
+    pub fn push_interleaved(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            self.sum_sq += frame.iter().map(|s| (*s as f64) * (*s as f64)).sum::<f64>();
+            self.filled += 1;
+            if self.filled == self.block_frames {
+                self.end_block();
+            }
+        }
+    }
+
+    /// Silent regions in seconds, ascending; a partial last block counts too.
+    pub fn finish(mut self) -> Vec<(f64, f64)> {
+        if self.filled > 0 {
+            self.end_block();
+        }
+        self.close_run();
+        self.regions
+    }
+
+    fn end_block(&mut self) {
+        let rms = (self.sum_sq / (self.filled * self.channels) as f64).sqrt();
+        if rms < SILENCE_RMS {
+            self.run_start.get_or_insert(self.frame);
+        } else {
+            self.close_run();
+        }
+        self.frame += self.filled as u64;
+        self.sum_sq = 0.0;
+        self.filled = 0;
+    }
+
+    fn close_run(&mut self) {
+        if let Some(start) = self.run_start.take() {
+            let (t0, t1) = (
+                start as f64 / self.sample_rate,
+                self.frame as f64 / self.sample_rate,
+            );
+            if t1 - t0 >= MIN_SILENCE_SECONDS {
+                self.regions.push((t0, t1));
+            }
+        }
+    }
+}
@@ -0,0 +1,526 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+/// CUE sheet positions are in frames of 1/75 s (CD sectors).
+const CUE_FRAMES_PER_SECOND: f64 = 75.0;
+/// A CUE sheet holds at most this many tracks.
+const CUE_MAX_TRACKS: usize = 99;
+
+/// A named time range; `start == end` for a point marker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    pub start: f64,
+    pub end: f64,
+    pub name: String,
+}
+
+impl Marker {
+    pub fn is_point(&self) -> bool {
+        self.end <= self.start
+    }
+}
+
+/// Marker exchange formats of other audio tools.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkerFormat {
+    /// Audacity label track: `start<TAB>end<TAB>label`, seconds.
+    Audacity,
+    /// REAPER Region/Marker Manager CSV, times as Minutes:Seconds.
+    Reaper,
+    /// CUE sheet; one track per range, so points and overlaps are lost.
+    Cue,
+    /// One `m:ss.mmm [m:ss.mmm] name` per line.
+    TimeList,
+}
+
+impl MarkerFormat {
+    pub const ALL: [MarkerFormat; 4] = [
+        MarkerFormat::Audacity,
+        MarkerFormat::Reaper,
+        MarkerFormat::Cue,
+        MarkerFormat::TimeList,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MarkerFormat::Audacity => "Audacity labels",
+            MarkerFormat::Reaper => "REAPER regions",
+            MarkerFormat::Cue => "CUE sheet",
+            MarkerFormat::TimeList => "Time list",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            MarkerFormat::Audacity => "txt",
+            MarkerFormat::Reaper => "csv",
+            MarkerFormat::Cue => "cue",
+            MarkerFormat::TimeList => "txt",
+        }
+    }
+}
+
+/// Write `markers` in `format`; CUE sheets name `audio` as their file.
+pub fn write(path: &Path, format: MarkerFormat, markers: &[Marker], audio: &Path) -> Result<()> {
+    let text = match format {
+        MarkerFormat::Audacity => to_audacity(markers),
+        MarkerFormat::Reaper => to_reaper(markers),
+        MarkerFormat::Cue => to_cue(markers, audio)?,
+        MarkerFormat::TimeList => to_time_list(markers),
+    };
+    std::fs::write(path, text).with_context(|| format!("write {}", path.display()))
+}
+
+/// Read markers in `format`; `duration` ends the last CUE track.
+pub fn read(path: &Path, format: MarkerFormat, duration: f64) -> Result<Vec<Marker>> {
+    let bytes = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    let text = String::from_utf8_lossy(&bytes);
+    let text = text.trim_start_matches('\u{feff}');
+    match format {
+        MarkerFormat::Audacity => parse_audacity(text),
+        MarkerFormat::Reaper => parse_reaper(text),
+        MarkerFormat::Cue => parse_cue(text, duration),
+        MarkerFormat::TimeList => parse_time_list(text),
+    }
+}
+
+/// `m:ss.mmm`; minutes are not wrapped into hours.
+pub fn format_time(secs: f64) -> String {
+    let total_ms = (secs.max(0.0) * 1000.0).round() as i64;
+    let minutes = total_ms / 60_000;
+    let seconds = (total_ms % 60_000) / 1000;
+    let millis = total_ms % 1000;
+    format!("{minutes}:{seconds:02}.{millis:03}")
+}
+
+/// Inverse of [`format_time`]: minutes, a colon, two-digit seconds and an
+/// optional fraction of any precision.
+pub fn parse_time(s: &str) -> Option<f64> {
+    let (minutes, seconds) = s.trim().split_once(':')?;
+    let (whole, frac) = seconds.split_once('.').unwrap_or((seconds, ""));
+    let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if minutes.is_empty() || !digits(minutes) || whole.len() != 2 || !digits(whole) || !digits(frac)
+    {
+        return None;
+    }
+    let minutes: f64 = minutes.parse().ok()?;
+    let seconds: f64 = seconds.parse().ok()?;
+    (seconds < 60.0).then_some(minutes * 60.0 + seconds)
+}
+
+fn to_audacity(markers: &[Marker]) -> String {
+    let mut out = String::new();
+    for m in markers {
+        let end = m.end.max(m.start);
+        let _ = writeln!(out, "{:.6}\t{:.6}\t{}", m.start, end, one_line(&m.name));
+    }
+    out
+}
+
+fn parse_audacity(text: &str) -> Result<Vec<Marker>> {
+    let mut markers = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        // `\` lines carry the frequency range of spectral labels.
+        if line.trim().is_empty() || line.starts_with('\\') {
+            continue;
+        }
+        let mut fields = line.splitn(3, '\t');
+        let mut seconds = || {
+            fields
+                .next()
+                .and_then(|f| f.trim().replace(',', ".").parse::<f64>().ok())
+                .filter(|t| t.is_finite() && *t >= 0.0)
+                .with_context(|| format!("line {}: expected start and end seconds", n + 1))
+        };
+        let (start, end) = (seconds()?, seconds()?);
+        markers.push(Marker {
+            start,
+            end: end.max(start),
+            name: fields.next().unwrap_or("").trim().to_owned(),
+        });
+    }
+    Ok(markers)
+}
+
+fn to_reaper(markers: &[Marker]) -> String {
+    let mut out = String::from("#,Name,Start,End,Length\n");
+    let (mut regions, mut points) = (0, 0);
+    for m in markers {
+        let name = csv_field(&m.name);
+        if m.is_point() {
+            points += 1;
+            let _ = writeln!(out, "M{points},{name},{},,", format_time(m.start));
+        } else {
+            regions += 1;
+            let _ = writeln!(
+                out,
+                "R{regions},{name},{},{},{}",
+                format_time(m.start),
+                format_time(m.end),
+                format_time(m.end - m.start)
+            );
+        }
+    }
+    out
+}
+
+fn parse_reaper(text: &str) -> Result<Vec<Marker>> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty());
+    let (_, header) = lines.next().context("empty file")?;
+    let header = split_csv(header);
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+            .with_context(|| {
+                format!("no {name} column; export from REAPER's Region/Marker Manager")
+            })
+    };
+    let (id, name, start, end) = (
+        column("#")?,
+        column("Name")?,
+        column("Start")?,
+        column("End")?,
+    );
+
+    let mut markers = Vec::new();
+    for (n, line) in lines {
+        let fields = split_csv(line);
+        let field = |i: usize| fields.get(i).map(|f| f.trim()).unwrap_or("");
+        let time = |i: usize| {
+            reaper_time(field(i)).with_context(|| {
+                format!(
+                    "line {}: cannot read time {:?}; set REAPER's time format to Minutes:Seconds or Seconds",
+                    n + 1,
+                    field(i)
+                )
+            })
+        };
+        let t0 = time(start)?;
+        let t1 = if field(id).starts_with(['M', 'm']) {
+            t0
+        } else {
+            time(end)?.max(t0)
+        };
+        markers.push(Marker {
+            start: t0,
+            end: t1,
+            name: field(name).to_owned(),
+        });
+    }
+    Ok(markers)
+}
+
+/// REAPER writes Minutes:Seconds as `m:ss.mmm` below an hour and
+/// `h:mm:ss.mmm` above; Seconds is a plain number.
+fn reaper_time(s: &str) -> Option<f64> {
+    if let Some(t) = parse_time(s) {
+        return Some(t);
+    }
+    if let Some((hours, rest)) = s.split_once(':')
+        && rest.find(':') == Some(2)
+        && let (Ok(hours), Some(t)) = (hours.parse::<u32>(), parse_time(rest))
+    {
+        return Some(hours as f64 * 3600.0 + t);
+    }
+    s.parse::<f64>().ok().filter(|t| t.is_finite() && *t >= 0.0)
+}
+
+fn to_cue(markers: &[Marker], audio: &Path) -> Result<String> {
+    let mut ranges: Vec<&Marker> = markers.iter().filter(|m| !m.is_point()).collect();
+    if ranges.len() > CUE_MAX_TRACKS {
+        bail!("a CUE sheet holds at most {CUE_MAX_TRACKS} tracks");
+    }
+    ranges.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let file = audio
+        .file_name()
+        .map(|f| f.to_string_lossy())
+        .unwrap_or_default();
+    let kind = match audio.extension().and_then(|e| e.to_str()) {
+        Some(e) if e.eq_ignore_ascii_case("mp3") => "MP3",
+        _ => "WAVE",
+    };
+    let mut out = format!("FILE \"{}\" {kind}\n", cue_text(&file));
+    let mut prev_end: Option<u64> = None;
+    for (k, m) in ranges.iter().enumerate() {
+        let start = cue_frames(m.start);
+        let _ = writeln!(out, "  TRACK {:02} AUDIO", k + 1);
+        let _ = writeln!(out, "    TITLE \"{}\"", cue_text(&m.name));
+        // A gap after the previous range becomes this track's pregap.
+        if let Some(end) = prev_end.filter(|e| *e < start) {
+            let _ = writeln!(out, "    INDEX 00 {}", format_cue_time(end));
+        }
+        let _ = writeln!(out, "    INDEX 01 {}", format_cue_time(start));
+        prev_end = Some(cue_frames(m.end));
+    }
+    Ok(out)
+}
+
+/// Each track runs from its INDEX 01 to the next track's pregap (INDEX 00)
+/// or start; the last one to `duration`.
+fn parse_cue(text: &str, duration: f64) -> Result<Vec<Marker>> {
+    // (title, INDEX 00, INDEX 01) per track.
+    let mut tracks: Vec<(Option<String>, Option<f64>, Option<f64>)> = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match command.to_ascii_uppercase().as_str() {
+            "TRACK" => tracks.push((None, None, None)),
+            "TITLE" => {
+                if let Some(track) = tracks.last_mut() {
+                    track.0 = Some(rest.trim().trim_matches('"').to_owned());
+                }
+            }
+            "INDEX" => {
+                let Some(track) = tracks.last_mut() else {
+                    continue;
+                };
+                let (number, time) = rest
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((rest, ""));
+                let t = parse_cue_time(time.trim())
+                    .with_context(|| format!("line {}: bad INDEX time {:?}", n + 1, time.trim()))?;
+                match number.parse::<u32>() {
+                    Ok(0) => track.1 = Some(t),
+                    Ok(1) => track.2 = Some(t),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut markers = Vec::new();
+    for (k, (title, _, start)) in tracks.iter().enumerate() {
+        let Some(start) = *start else {
+            bail!("track {} has no INDEX 01", k + 1);
+        };
+        let end = tracks
+            .get(k + 1)
+            .and_then(|(_, pregap, next)| pregap.or(*next))
+            .unwrap_or(duration)
+            .max(start);
+        markers.push(Marker {
+            start,
+            end,
+            name: title.clone().unwrap_or_else(|| format!("Track {}", k + 1)),
+        });
+    }
+    Ok(markers)
+}
+
+fn cue_frames(secs: f64) -> u64 {
+    (secs.max(0.0) * CUE_FRAMES_PER_SECOND).round() as u64
+}
+
+/// `mm:ss:ff`; minutes may exceed 99.
+fn format_cue_time(frames: u64) -> String {
+    let per_second = CUE_FRAMES_PER_SECOND as u64;
+    let seconds = frames / per_second;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 60,
+        seconds % 60,
+        frames % per_second
+    )
+}
+
+fn parse_cue_time(s: &str) -> Option<f64> {
+    let mut parts = s.split(':').map(|p| p.parse::<u64>().ok());
+    let (Some(Some(m)), Some(Some(sec)), Some(Some(f)), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    (sec < 60 && (f as f64) < CUE_FRAMES_PER_SECOND)
+        .then(|| (m * 60 + sec) as f64 + f as f64 / CUE_FRAMES_PER_SECOND)
+}
+
+fn to_time_list(markers: &[Marker]) -> String {
+    let mut out = String::new();
+    for m in markers {
+        let name = one_line(&m.name);
+        if m.is_point() {
+            let _ = writeln!(out, "{} {name}", format_time(m.start));
+        } else {
+            let _ = writeln!(
+                out,
+                "{} {} {name}",
+                format_time(m.start),
+                format_time(m.end)
+            );
+        }
+    }
+    out
+}
+
+/// Lines of `start [end] name`; blank lines and `#` comments are skipped.
+fn parse_time_list(text: &str) -> Result<Vec<Marker>> {
+    let mut markers = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (first, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let start = parse_time(first)
+            .with_context(|| format!("line {}: expected m:ss.mmm, found {first:?}", n + 1))?;
+        let rest = rest.trim_start();
+        let (second, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let (end, name) = match parse_time(second) {
+            Some(end) => (end.max(start), after.trim()),
+            None => (start, rest.trim()),
+        };
+        markers.push(Marker {
+            start,
+            end,
+            name: name.to_owned(),
+        });
+    }
+    Ok(markers)
+}
+
+fn one_line(name: &str) -> String {
+    name.replace(['\t', '\r', '\n'], " ")
+}
+
+fn cue_text(s: &str) -> String {
+    one_line(s).replace('"', "'")
+}
+
+fn csv_field(s: &str) -> String {
+    let s = one_line(s);
+    if s.contains([',', '"']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s
+    }
+}
+
+/// Split one CSV line, honouring double-quoted fields with `""` escapes.
+fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(start: f64, end: f64, name: &str) -> Marker {
+        Marker {
+            start,
+            end,
+            name: name.to_owned(),
+        }
+    }
+
+    fn assert_close(a: &[Marker], b: &[Marker]) {
+        assert_eq!(a.len(), b.len(), "{a:?} vs {b:?}");
+        for (x, y) in a.iter().zip(b) {
+            assert!((x.start - y.start).abs() < 1e-3, "{x:?} vs {y:?}");
+            assert!((x.end - y.end).abs() < 1e-3, "{x:?} vs {y:?}");
+            assert_eq!(x.name, y.name);
+        }
+    }
+
+    #[test]
+    fn time_round_trips() {
+        for secs in [0.0, 0.001, 9.5, 59.999, 60.0, 61.25, 3599.999, 3723.5] {
+            let parsed = parse_time(&format_time(secs)).unwrap();
+            assert!((parsed - secs).abs() < 5e-4, "{secs} -> {parsed}");
+        }
+        assert_eq!(format_time(3723.5), "62:03.500");
+        assert_eq!(parse_time(" 1:02.25 "), Some(62.25));
+        assert_eq!(parse_time("0:07"), Some(7.0));
+    }
+
+    #[test]
+    fn time_rejects_malformed() {
+        for s in [
+            "", "12", ":05.0", "1:5.0", "1:60.0", "1:05.x", "-1:05.0", "a:05.0",
+        ] {
+            assert_eq!(parse_time(s), None, "{s:?}");
+        }
+    }
+
+    #[test]
+    fn reaper_times() {
+        assert_eq!(reaper_time("1:02:03.500"), Some(3723.5));
+        assert_eq!(reaper_time("2:03.500"), Some(123.5));
+        assert_eq!(reaper_time("12.25"), Some(12.25));
+        assert_eq!(reaper_time("-1"), None);
+        assert_eq!(reaper_time("1:2:03.5"), None);
+    }
+
+    #[test]
+    fn reaper_round_trips() {
+        let markers = [
+            marker(1.5, 1.5, "Count, in"),
+            marker(4.0, 3723.5, "Verse \"A\""),
+        ];
+        assert_close(&parse_reaper(&to_reaper(&markers)).unwrap(), &markers);
+    }
+
+    #[test]
+    fn csv_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(
+            split_csv("R1,\"a,\"\"b\"\"\",1:00.000"),
+            ["R1", "a,\"b\"", "1:00.000"]
+        );
+    }
+
+    #[test]
+    fn cue_round_trips_with_pregaps() {
+        // The gap between the first two tracks is written as an INDEX 00
+        // pregap; the touching third track gets none.
+        let markers = [
+            marker(2.0, 8.0, "Intro"),
+            marker(10.0, 20.0, "Verse"),
+            marker(20.0, 30.0, "Chorus"),
+        ];
+        let cue = to_cue(&markers, Path::new("take.wav")).unwrap();
+        assert_eq!(cue.matches("INDEX 00").count(), 1);
+        assert!(cue.contains("INDEX 00 00:08:00\n    INDEX 01 00:10:00"));
+        assert_close(&parse_cue(&cue, 30.0).unwrap(), &markers);
+    }
+
+    #[test]
+    fn cue_times() {
+        assert_eq!(format_cue_time(cue_frames(61.0 + 2.0 / 75.0)), "01:01:02");
+        assert_eq!(parse_cue_time("01:01:02"), Some(61.0 + 2.0 / 75.0));
+        assert_eq!(parse_cue_time("00:60:00"), None);
+        assert_eq!(parse_cue_time("00:00:75"), None);
+        assert_eq!(parse_cue_time("00:00"), None);
+    }
+
+    #[test]
+    fn audacity_and_time_list_round_trip() {
+        let markers = [marker(0.5, 0.5, "Click"), marker(1.25, 62.0, "Take 2")];
+        assert_close(&parse_audacity(&to_audacity(&markers)).unwrap(), &markers);
+        assert_close(&parse_time_list(&to_time_list(&markers)).unwrap(), &markers);
+    }
+}
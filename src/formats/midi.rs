@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::analysis::beats::TempoMap;
+use crate::analysis::transcribe::NoteEvent;
+
+/// Ticks per quarter note.
+const PPQ: u32 = 480;
+/// Tempo written when there is no tempo map.
+const DEFAULT_BPM: f64 = 120.0;
+
+/// Write `notes` as a format-0 Standard MIDI File.
+///
+/// With a tempo map, every beat becomes a quarter note and gets its own tempo
+/// event, so the file plays back in real time while notation programs see the
+/// bars of the recording. Tick 0 is the start of the bar containing `from`.
+pub fn write_notes(
+    path: &Path,
+    notes: &[NoteEvent],
+    map: Option<&TempoMap>,
+    from: f64,
+) -> Result<()> {
+    let origin_beat = map.map(|m| {
+        let beat = m.beat_at(from).floor() as i64;
+        beat - beat.rem_euclid(m.beats_per_bar.max(1) as i64)
+    });
+    let to_ticks = |t: f64| -> u32 {
+        let beats = match (map, origin_beat) {
+            (Some(m), Some(b0)) => m.beat_at(t) - b0 as f64,
+            _ => (t - from) * DEFAULT_BPM / 60.0,
+        };
+        (beats * PPQ as f64).round().max(0.0) as u32
+    };
+
+    // (tick, order, bytes): at equal ticks meta events go first, then note-offs.
+    let mut events: Vec<(u32, u8, Vec<u8>)> = Vec::new();
+    let beats_per_bar = map.map(|m| m.beats_per_bar).unwrap_or(4).clamp(1, 255) as u8;
+    events.push((0, 0, vec![0xFF, 0x58, 0x04, beats_per_bar, 2, 24, 8]));
+    let end = notes.iter().map(|n| n.end).fold(from, f64::max);
+    match (map, origin_beat) {
+        (Some(m), Some(b0)) => {
+            let last = m.beat_at(end).ceil() as i64;
+            for beat in b0..=last {
+                let seconds = m.time_of_beat(beat + 1) - m.time_of_beat(beat);
+                let tick = (beat - b0) as u32 * PPQ;
+                events.push((tick, 0, tempo_event(seconds)));
+            }
+        }
+        _ => events.push((0, 0, tempo_event(60.0 / DEFAULT_BPM))),
+    }
+    for note in notes {
+        let on = to_ticks(note.start);
+        let off = to_ticks(note.end).max(on + 1);
+        events.push((on, 2, vec![0x90, note.midi, note.velocity]));
+        events.push((off, 1, vec![0x80, note.midi, 0]));
+    }
+    events.sort_by_key(|(tick, order, _)| (*tick, *order));
+
+    let mut track = Vec::new();
+    let mut last_tick = 0;
+    for (tick, _, bytes) in events {
+        write_vlq(&mut track, tick - last_tick);
+        track.extend_from_slice(&bytes);
+        last_tick = tick;
+    }
+    write_vlq(&mut track, 0);
+    track.extend_from_slice(&[0xFF, 0x2F, 0x00]);
+
+    let mut file = Vec::with_capacity(track.len() + 22);
+    file.extend_from_slice(b"MThd");
+    file.extend_from_slice(&6u32.to_be_bytes());
+    file.extend_from_slice(&0u16.to_be_bytes()); // format 0
+    file.extend_from_slice(&1u16.to_be_bytes()); // one track
+    file.extend_from_slice(&(PPQ as u16).to_be_bytes());
+    file.extend_from_slice(b"MTrk");
+    file.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    file.extend_from_slice(&track);
+
+    std::fs::write(path, file).with_context(|| format!("writing {}", path.display()))
+}
+
+/// Set-tempo meta event for a quarter note lasting `seconds`.
+fn tempo_event(seconds: f64) -> Vec<u8> {
+    let micros = (seconds * 1e6).round().clamp(1.0, 0xFF_FFFF as f64) as u32;
+    let b = micros.to_be_bytes();
+    vec![0xFF, 0x51, 0x03, b[1], b[2], b[3]]
+}
+
+/// MIDI variable-length quantity.
+fn write_vlq(out: &mut Vec<u8>, mut value: u32) {
+    let mut bytes = [0u8; 5];
+    let mut n = 0;
+    loop {
+        bytes[n] = (value & 0x7F) as u8;
+        n += 1;
+        value >>= 7;
+        if value == 0 {
+            break;
+        }
+    }
+    for i in (0..n).rev() {
+        out.push(if i > 0 { bytes[i] | 0x80 } else { bytes[i] });
+    }
+}
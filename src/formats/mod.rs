@@ -0,0 +1,3 @@
+pub mod markers;
+pub mod midi;
+pub mod project;
@@ -0,0 +1,246 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::analysis::beats::TempoMap;
+use crate::audio::decode::fingerprint_file;
+use crate::library::LoopLibrary;
+
+/// File extension of project documents.
+pub const EXTENSION: &str = "loopah";
+/// Schema version written by this build.
+pub const VERSION: u64 = 1;
+/// Upgrades a document from version `i + 1` to `i + 2`. A schema change bumps
+/// `VERSION` and appends the step that rewrites older documents, so files
+/// from every earlier release keep opening.
+const MIGRATIONS: [fn(&mut Value); (VERSION - 1) as usize] = [];
+/// How deep below the project folder a moved audio file is searched for.
+const SEARCH_DEPTH: usize = 3;
+const AUDIO_EXTENSIONS: [&str; 5] = ["m4a", "aac", "mp3", "wav", "flac"];
+
+/// A practice setup: the recording it belongs to and everything set up on it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    pub version: u64,
+    pub audio: AudioRef,
+    /// Loops (with their notes) and hot cues.
+    pub library: LoopLibrary,
+    /// A/B in seconds.
+    #[serde(default)]
+    pub loop_range: Option<(f64, f64)>,
+    #[serde(default)]
+    pub tempo_map: Option<TempoMap>,
+    #[serde(default)]
+    pub playback: Playback,
+}
+
+/// Where the recording is, and how to recognise it if it has moved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioRef {
+    /// Relative to the project file, `/`-separated; absolute when the two are
+    /// on different drives.
+    pub path: String,
+    pub fingerprint: u64,
+    /// File size in bytes; narrows the fingerprint search.
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Playback {
+    pub speed: f32,
+    pub pitch: f32,
+    pub volume: f32,
+    pub metronome: bool,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            pitch: 0.0,
+            volume: 1.0,
+            metronome: false,
+        }
+    }
+}
+
+impl AudioRef {
+    /// Reference `audio` (with the given fingerprint) from a project saved at `project`.
+    pub fn new(project: &Path, audio: &Path, fingerprint: u64) -> Result<Self> {
+        let size = std::fs::metadata(audio)
+            .with_context(|| format!("read {}", audio.display()))?
+            .len();
+        let audio = std::path::absolute(audio)?;
+        let dir = std::path::absolute(project_dir(project))?;
+        let path = relative_path(&dir, &audio).unwrap_or_else(|| audio.display().to_string());
+        Ok(Self {
+            path,
+            fingerprint,
+            size,
+        })
+    }
+
+    /// The recording for a project at `project`: the referenced path if its
+    /// content still matches, otherwise a file with the same fingerprint in
+    /// or below the project folder, otherwise the referenced path if it
+    /// exists at all (the recording was edited). Candidates of the right size
+    /// are hashed whole, so this blocks for as long as that takes.
+    pub fn resolve(&self, project: &Path) -> Option<PathBuf> {
+        let dir = project_dir(project);
+        let referenced = dir.join(&self.path);
+        let matches = |path: &Path| {
+            std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.len() == self.size)
+                && fingerprint_file(path).is_ok_and(|hash| hash == self.fingerprint)
+        };
+        if matches(&referenced) {
+            return Some(referenced);
+        }
+        find_file(dir, SEARCH_DEPTH, &matches)
+            .or_else(|| referenced.is_file().then_some(referenced))
+    }
+}
+
+/// Read a project, upgrading documents written by older versions.
+pub fn read(path: &Path) -> Result<Project> {
+    let bytes = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    let mut doc: Value = serde_json::from_slice(&bytes).context("not a JSON document")?;
+    migrate(&mut doc)?;
+    serde_json::from_value(doc).context("invalid project")
+}
+
+pub fn write(path: &Path, project: &Project) -> Result<()> {
+    let json = serde_json::to_string_pretty(project)?;
+    std::fs::write(path, json + "\n").with_context(|| format!("write {}", path.display()))
+}
+
+fn migrate(doc: &mut Value) -> Result<()> {
+    let version = doc
+        .get("version")
+        .and_then(Value::as_u64)
+        .context("missing schema version")?;
+    if version == 0 {
+        bail!("invalid schema version 0");
+    }
+    if version > VERSION {
+        bail!(
+            "project is from a newer Loopah (schema {version}, this build reads up to {VERSION})"
+        );
+    }
+    for step in &MIGRATIONS[(version - 1) as usize..] {
+        step(doc);
+    }
+    doc["version"] = VERSION.into();
+    Ok(())
+}
+
+fn project_dir(project: &Path) -> &Path {
+    project.parent().unwrap_or(Path::new("."))
+}
+
+/// `target` relative to the directory `base`, both absolute; `None` when they
+/// share no root.
+fn relative_path(base: &Path, target: &Path) -> Option<String> {
+    let base: Vec<Component> = base.components().collect();
+    let target: Vec<Component> = target.components().collect();
+    let common = base.iter().zip(&target).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return None;
+    }
+    let mut parts = vec!["..".to_owned(); base.len() - common];
+    for c in &target[common..] {
+        parts.push(c.as_os_str().to_str()?.to_owned());
+    }
+    Some(parts.join("/"))
+}
+
+/// First audio file in `dir` (or `depth` levels below) that `matches`.
+fn find_file(dir: &Path, depth: usize, matches: &dyn Fn(&Path) -> bool) -> Option<PathBuf> {
+    let mut subdirs = Vec::new();
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            // Skips `.git` and similar.
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                subdirs.push(path);
+            }
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+            && matches(&path)
+        {
+            return Some(path);
+        }
+    }
+    if depth == 0 {
+        return None;
+    }
+    subdirs
+        .iter()
+        .find_map(|sub| find_file(sub, depth - 1, matches))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn project() -> Project {
+        Project {
+            version: VERSION,
+            audio: AudioRef {
+                path: "take.wav".to_owned(),
+                fingerprint: 42,
+                size: 1000,
+            },
+            library: LoopLibrary::default(),
+            loop_range: Some((1.5, 4.0)),
+            tempo_map: None,
+            playback: Playback::default(),
+        }
+    }
+
+    #[test]
+    fn migrate_accepts_current_version() {
+        let mut doc = serde_json::to_value(project()).unwrap();
+        let before = doc.clone();
+        migrate(&mut doc).unwrap();
+        assert_eq!(doc, before);
+        assert_eq!(serde_json::from_value::<Project>(doc).unwrap(), project());
+    }
+
+    #[test]
+    fn migrate_rejects_newer_version() {
+        let mut doc = json!({ "version": VERSION + 1 });
+        let err = migrate(&mut doc).unwrap_err().to_string();
+        assert!(err.contains("newer Loopah"), "{err}");
+    }
+
+    #[test]
+    fn migrate_rejects_bad_versions() {
+        for mut doc in [
+            json!({}),
+            json!({ "version": 0 }),
+            json!({ "version": -1 }),
+            json!({ "version": "1" }),
+            json!([]),
+        ] {
+            assert!(migrate(&mut doc).is_err(), "{doc}");
+        }
+    }
+
+    #[test]
+    fn missing_optional_fields_default() {
+        let mut doc = serde_json::to_value(project()).unwrap();
+        for key in ["loop_range", "tempo_map", "playback"] {
+            doc.as_object_mut().unwrap().remove(key);
+        }
+        migrate(&mut doc).unwrap();
+        let read: Project = serde_json::from_value(doc).unwrap();
+        assert_eq!(read.loop_range, None);
+        assert_eq!(read.playback, Playback::default());
+    }
+}
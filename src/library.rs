@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+/// Colours handed to new loops in turn.
+const LOOP_COLORS: [[u8; 3]; 8] = [
+    [120, 180, 255],
+    [255, 160, 90],
+    [130, 210, 130],
+    [230, 120, 200],
+    [240, 210, 90],
+    [150, 130, 240],
+    [90, 210, 210],
+    [240, 110, 110],
+];
+
+/// A named A/B range with the playback settings it is practised at.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedLoop {
+    pub name: String,
+    pub start: f64,
+    pub end: f64,
+    pub color: [u8; 3],
+    /// Playback rate, 1 = original tempo.
+    pub speed: f32,
+    /// Transposition in semitones.
+    pub pitch: f32,
+    /// Linear gain.
+    pub volume: f32,
+    /// Free-text annotation, e.g. what to listen for.
+    #[serde(default)]
+    pub notes: String,
+}
+
+/// Number of hot cue slots, bound to keys 1–9.
+pub const CUE_SLOTS: usize = 9;
+
+/// The loops and cue points saved for one file; loops in the user's order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LoopLibrary {
+    pub loops: Vec<NamedLoop>,
+    /// Index of the loop that follows A/B and playback changes.
+    pub active: Option<usize>,
+    /// Hot cue positions in seconds; slot 0 is key 1.
+    pub cues: [Option<f64>; CUE_SLOTS],
+}
+
+impl LoopLibrary {
+    /// Append a loop with a default name and the next colour; it becomes active.
+    pub fn add(&mut self, start: f64, end: f64, speed: f32, pitch: f32, volume: f32) {
+        let n = self.loops.len();
+        self.loops.push(NamedLoop {
+            name: format!("Loop {}", n + 1),
+            start,
+            end,
+            color: LOOP_COLORS[n % LOOP_COLORS.len()],
+            speed,
+            pitch,
+            volume,
+            notes: String::new(),
+        });
+        self.active = Some(n);
+    }
+
+    pub fn active_mut(&mut self) -> Option<&mut NamedLoop> {
+        self.active.and_then(|i| self.loops.get_mut(i))
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index >= self.loops.len() {
+            return;
+        }
+        self.loops.remove(index);
+        self.active = match self.active {
+            Some(a) if a == index => None,
+            Some(a) if a > index => Some(a - 1),
+            other => other,
+        };
+    }
+
+    /// Swap loop `index` with its neighbour above (`up`) or below; the active
+    /// loop stays selected.
+    pub fn move_loop(&mut self, index: usize, up: bool) {
+        let Some(other) = (if up {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1)
+        })
+        .filter(|o| *o < self.loops.len()) else {
+            return;
+        };
+        self.loops.swap(index, other);
+        self.active = match self.active {
+            Some(a) if a == index => Some(other),
+            Some(a) if a == other => Some(index),
+            a => a,
+        };
+    }
+}
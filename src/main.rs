@@ -1,12 +1,27 @@
+mod analysis;
 mod app;
 mod audio;
+mod formats;
+mod library;
+mod session;
 mod ui;
 
 fn main() -> eframe::Result<()> {
-    let native_options = eframe::NativeOptions::default();
+    let session = session::Session::load();
+    let mut viewport = eframe::egui::ViewportBuilder::default();
+    if let Some(window) = session.window {
+        viewport = viewport.with_inner_size(window.size);
+        if let Some(position) = window.position {
+            viewport = viewport.with_position(position);
+        }
+    }
+    let native_options = eframe::NativeOptions {
+        viewport,
+        ..Default::default()
+    };
     eframe::run_native(
         "Loopah",
         native_options,
-        Box::new(|cc| Ok(Box::new(app::LoopahApp::new(cc)))),
+        Box::new(|cc| Ok(Box::new(app::LoopahApp::new(cc, session)))),
     )
 }
@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{FollowMode, SnapMode};
+use crate::library::LoopLibrary;
+use crate::ui::waveform::WaveformLanes;
+
+/// Files remembered at most; the least recently opened are dropped first.
+const MAX_FILES: usize = 200;
+
+/// Everything restored at the next start, saved as JSON in the config directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub last_file: Option<PathBuf>,
+    pub window: Option<WindowGeometry>,
+    pub prefs: Prefs,
+    /// Per-file state, most recently opened last.
+    pub files: Vec<FileState>,
+}
+
+/// Window placement in points; the position is unknown on some platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub position: Option<[f32; 2]>,
+    pub size: [f32; 2],
+}
+
+/// Display and editing preferences that apply to every file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Prefs {
+    pub show_beat_grid: bool,
+    pub show_chords: bool,
+    pub show_sections: bool,
+    pub show_pitch: bool,
+    pub show_spectrogram: bool,
+    pub metronome: bool,
+    pub scrub_audio: bool,
+    pub follow_mode: FollowMode,
+    pub snap_mode: SnapMode,
+    pub snap_zero_crossings: bool,
+    pub waveform_lanes: WaveformLanes,
+    pub denoise_strength: f32,
+}
+
+impl Default for Prefs {
+    fn default() -> Self {
+        Self {
+            show_beat_grid: true,
+            show_chords: true,
+            show_sections: true,
+            show_pitch: true,
+            show_spectrogram: true,
+            metronome: false,
+            scrub_audio: true,
+            follow_mode: FollowMode::Page,
+            snap_mode: SnapMode::Off,
+            snap_zero_crossings: false,
+            waveform_lanes: WaveformLanes::default(),
+            denoise_strength: 1.5,
+        }
+    }
+}
+
+/// What was set up for one audio file, identified by path and content.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileState {
+    pub path: PathBuf,
+    pub fingerprint: u64,
+    pub library: LoopLibrary,
+    /// A/B in seconds.
+    pub loop_range: Option<(f64, f64)>,
+    /// Visible time range in seconds.
+    pub view: (f64, f64),
+    pub speed: f32,
+    pub pitch: f32,
+    pub volume: f32,
+}
+
+impl Session {
+    /// The saved session, or a default one when there is none or it is unreadable.
+    pub fn load() -> Self {
+        let Some(path) = session_path() else {
+            return Self::default();
+        };
+        match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                eprintln!("Ignoring session {}: {e}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Write `json` (a serialized session) to the config directory.
+    pub fn store(json: &str) -> Result<()> {
+        let path = session_path().context("no config directory")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+        }
+        // Write then rename, so a crash mid-write never leaves a truncated file.
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json).with_context(|| format!("write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("replace {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Saved state for a file: the same path and content, else the same
+    /// content under another path (the file was moved or renamed).
+    pub fn file(&self, path: &Path, fingerprint: u64) -> Option<&FileState> {
+        let same_content = || self.files.iter().filter(|f| f.fingerprint == fingerprint);
+        same_content()
+            .rfind(|f| f.path == path)
+            .or_else(|| same_content().next_back())
+    }
+
+    /// Replace the entry for `state`'s path and move it to the most recent place.
+    pub fn remember_file(&mut self, state: FileState) {
+        self.files.retain(|f| f.path != state.path);
+        self.files.push(state);
+        if self.files.len() > MAX_FILES {
+            let excess = self.files.len() - MAX_FILES;
+            self.files.drain(..excess);
+        }
+    }
+}
+
+/// `<config dir>/loopah/session.json`.
+fn session_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("loopah").join("session.json"))
+}
+
+fn config_dir() -> Option<PathBuf> {
+    let env_dir = |name: &str| {
+        std::env::var_os(name)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+    if cfg!(target_os = "windows") {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+    }
+}
@@ -0,0 +1,86 @@
+use eframe::egui;
+use eframe::egui::{Color32, Stroke};
+use egui_plot::{PlotBounds, PlotPoint, Polygon, Text};
+
+use crate::analysis::chords::ChordTimeline;
+use crate::ui::waveform::{WaveformResult, draw_playhead, interaction_result, timeline_plot};
+
+/// Rough label width per character, to skip labels that would not fit.
+const CHAR_WIDTH_PX: f64 = 8.0;
+
+/// Inputs for the chord lane; X bounds are shared with the waveform.
+pub struct ChordLaneView<'a> {
+    pub timeline: &'a ChordTimeline,
+    pub duration_s: f64,
+    pub x_min: f64,
+    pub x_max: f64,
+    pub playhead_sec: Option<f64>,
+    /// Semitones the player is shifting by; labels follow what is heard.
+    pub transpose: i32,
+    pub allow_pan: bool,
+}
+
+/// Draw one block per chord span with its symbol, above the waveform.
+pub fn draw_chord_lane(ui: &mut egui::Ui, view: ChordLaneView<'_>) -> WaveformResult {
+    let ChordLaneView {
+        timeline,
+        duration_s,
+        x_min,
+        x_max,
+        playhead_sec,
+        transpose,
+        allow_pan,
+    } = view;
+
+    let shift_down = ui.input(|i| i.modifiers.shift);
+    let width_px = ui.available_width().max(1.0) as f64;
+    let px_per_sec = width_px / (x_max - x_min).max(1e-9);
+
+    let response = timeline_plot("chord_lane", 28.0, allow_pan && !shift_down)
+        .show_grid(false)
+        .show_y(false)
+        .y_axis_formatter(|_, _| String::new())
+        .show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(PlotBounds::from_min_max([x_min, 0.0], [x_max, 1.0]));
+
+            let a = timeline.spans.partition_point(|s| s.end < x_min);
+            for (i, span) in timeline.spans[a..].iter().enumerate() {
+                if span.start > x_max {
+                    break;
+                }
+                let Some(chord) = span.chord.map(|c| c.transposed(transpose)) else {
+                    continue;
+                };
+                let shade = if (a + i) % 2 == 0 { 40 } else { 24 };
+                plot_ui.polygon(
+                    Polygon::new(
+                        "chord",
+                        vec![
+                            [span.start, 0.05],
+                            [span.end, 0.05],
+                            [span.end, 0.95],
+                            [span.start, 0.95],
+                        ],
+                    )
+                    .fill_color(Color32::from_white_alpha(shade))
+                    .stroke(Stroke::NONE),
+                );
+
+                let symbol = chord.symbol();
+                let visible = span.end.min(x_max) - span.start.max(x_min);
+                if visible * px_per_sec >= symbol.len() as f64 * CHAR_WIDTH_PX {
+                    // Keep the label on screen while its span is partly scrolled off.
+                    let x = span.start.max(x_min);
+                    plot_ui.text(
+                        Text::new("chord_label", PlotPoint::new(x, 0.5), symbol)
+                            .anchor(egui::Align2::LEFT_CENTER),
+                    );
+                }
+            }
+
+            draw_playhead(plot_ui, playhead_sec, duration_s);
+            plot_ui.plot_bounds()
+        });
+
+    interaction_result(ui, &response, duration_s, shift_down)
+}
@@ -0,0 +1,176 @@
+use eframe::egui;
+use eframe::egui::{Color32, Stroke};
+use egui_plot::{PlotBounds, PlotPoint, Polygon, Text};
+
+use crate::library::LoopLibrary;
+use crate::ui::waveform::{WaveformResult, draw_playhead, interaction_result, timeline_plot};
+
+/// Height of one loop lane in pixels.
+const LANE_PX: f32 = 18.0;
+
+/// What the user asked the sidebar to do; renames and colours are edited in place.
+#[derive(Debug, Clone, Copy)]
+pub enum LoopAction {
+    Add,
+    Activate(usize),
+    Remove(usize),
+    Move(usize, bool),
+}
+
+/// List of saved loops with controls to add, activate, rename, annotate,
+/// recolour, reorder and delete them.
+pub fn draw_loop_sidebar(ui: &mut egui::Ui, library: &mut LoopLibrary) -> Option<LoopAction> {
+    let mut action = None;
+    ui.horizontal(|ui| {
+        ui.heading("Loops");
+        if ui
+            .button("＋")
+            .on_hover_text("Save the current A/B as a new loop")
+            .clicked()
+        {
+            action = Some(LoopAction::Add);
+        }
+    });
+    ui.separator();
+    if library.loops.is_empty() {
+        ui.label("No saved loops yet.");
+    }
+    let count = library.loops.len();
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for (i, lp) in library.loops.iter_mut().enumerate() {
+            let active = library.active == Some(i);
+            ui.horizontal(|ui| {
+                ui.color_edit_button_srgb(&mut lp.color);
+                ui.add(egui::TextEdit::singleline(&mut lp.name).desired_width(110.0));
+            });
+            ui.add(
+                egui::TextEdit::multiline(&mut lp.notes)
+                    .hint_text("Notes")
+                    .desired_rows(1)
+                    .desired_width(150.0),
+            );
+            ui.horizontal(|ui| {
+                let summary = format!("{:.1}–{:.1}s ×{:.2}", lp.start, lp.end, lp.speed);
+                if ui
+                    .selectable_label(active, summary)
+                    .on_hover_text("Make this the active loop; click again to detach A/B from it")
+                    .clicked()
+                {
+                    action = Some(LoopAction::Activate(i));
+                }
+                if ui
+                    .add_enabled(i > 0, egui::Button::new("▲").small())
+                    .clicked()
+                {
+                    action = Some(LoopAction::Move(i, true));
+                }
+                if ui
+                    .add_enabled(i + 1 < count, egui::Button::new("▼").small())
+                    .clicked()
+                {
+                    action = Some(LoopAction::Move(i, false));
+                }
+                if ui.small_button("🗑").on_hover_text("Delete loop").clicked() {
+                    action = Some(LoopAction::Remove(i));
+                }
+            });
+            ui.add_space(4.0);
+        }
+    });
+    action
+}
+
+/// Inputs for the loop lanes; X bounds are shared with the waveform.
+pub struct LoopLanesView<'a> {
+    pub library: &'a LoopLibrary,
+    pub duration_s: f64,
+    pub x_min: f64,
+    pub x_max: f64,
+    pub playhead_sec: Option<f64>,
+    pub allow_pan: bool,
+}
+
+/// One labelled lane per saved loop. Also returns the loop whose block was
+/// clicked, if any.
+pub fn draw_loop_lanes(
+    ui: &mut egui::Ui,
+    view: LoopLanesView<'_>,
+) -> (WaveformResult, Option<usize>) {
+    let LoopLanesView {
+        library,
+        duration_s,
+        x_min,
+        x_max,
+        playhead_sec,
+        allow_pan,
+    } = view;
+
+    let lanes = library.loops.len().max(1);
+    let shift_down = ui.input(|i| i.modifiers.shift);
+    let response = timeline_plot(
+        "loop_lanes",
+        LANE_PX * lanes as f32 + 8.0,
+        allow_pan && !shift_down,
+    )
+    .show_grid(false)
+    .show_y(false)
+    .y_axis_formatter(|_, _| String::new())
+    .show(ui, |plot_ui| {
+        // Lane k spans y in [-(k + 1), -k], first loop on top.
+        plot_ui.set_plot_bounds(PlotBounds::from_min_max(
+            [x_min, -(lanes as f64)],
+            [x_max, 0.0],
+        ));
+        for (k, lp) in library.loops.iter().enumerate() {
+            if lp.end < x_min || lp.start > x_max {
+                continue;
+            }
+            let [r, g, b] = lp.color;
+            let alpha = if library.active == Some(k) { 150 } else { 60 };
+            let (top, bottom) = (-(k as f64) - 0.1, -(k as f64) - 0.9);
+            plot_ui.polygon(
+                Polygon::new(
+                    "loop_lane",
+                    vec![
+                        [lp.start, bottom],
+                        [lp.end, bottom],
+                        [lp.end, top],
+                        [lp.start, top],
+                    ],
+                )
+                .fill_color(Color32::from_rgba_unmultiplied(r, g, b, alpha))
+                .stroke(Stroke::new(1.0, Color32::from_rgb(r, g, b))),
+            );
+            plot_ui.text(
+                Text::new(
+                    "loop_label",
+                    PlotPoint::new(lp.start.max(x_min), -(k as f64) - 0.5),
+                    lp.name.as_str(),
+                )
+                .anchor(egui::Align2::LEFT_CENTER),
+            );
+        }
+        draw_playhead(plot_ui, playhead_sec, duration_s);
+        plot_ui.plot_bounds()
+    });
+
+    let hit = response
+        .response
+        .clicked()
+        .then(|| response.response.interact_pointer_pos())
+        .flatten()
+        .and_then(|pos| {
+            let p = response.transform.value_from_position(pos);
+            let k = (-p.y).floor();
+            (k >= 0.0).then_some(k as usize).filter(|k| {
+                library
+                    .loops
+                    .get(*k)
+                    .is_some_and(|lp| (lp.start..=lp.end).contains(&p.x))
+            })
+        });
+    (
+        interaction_result(ui, &response, duration_s, shift_down),
+        hit,
+    )
+}
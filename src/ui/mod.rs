@@ -1 +1,5 @@
+pub mod chords;
+pub mod loops;
+pub mod overview;
+pub mod spectrogram;
 pub mod waveform;
@@ -0,0 +1,121 @@
+use eframe::egui;
+use eframe::egui::{Color32, PointerButton, Sense, Stroke, StrokeKind};
+
+use crate::audio::decode::DecodedInfo;
+
+/// Return value for the overview strip: a new visible window if the user moved it.
+pub struct OverviewResult {
+    pub view: Option<(f64, f64)>,
+}
+
+/// Draw a thin full-file overview with the visible window highlighted.
+/// - Drag the window to move the main view.
+/// - Click outside the window to center the view there.
+pub fn draw_overview(
+    ui: &mut egui::Ui,
+    info: &DecodedInfo,
+    x_min: f64,
+    x_max: f64,
+    playhead_sec: Option<f64>,
+    loop_range: Option<(f64, f64)>,
+) -> OverviewResult {
+    let desired = egui::vec2(ui.available_width(), 36.0);
+    let (rect, response) = ui.allocate_exact_size(desired, Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+
+    if info.total_frames == 0 || info.sample_rate == 0 {
+        return OverviewResult { view: None };
+    }
+    let duration_s = info.total_frames as f64 / info.sample_rate as f64;
+    let columns = rect.width().max(1.0) as usize;
+    let frames_per_col = info.total_frames as f64 / columns as f64;
+    let Some(level) = info
+        .peaks
+        .level_for(frames_per_col)
+        .or(info.peaks.levels.first())
+    else {
+        return OverviewResult { view: None };
+    };
+    if level.is_empty() {
+        return OverviewResult { view: None };
+    }
+    let n = level.len();
+
+    let to_x = |sec: f64| rect.left() + (sec / duration_s).clamp(0.0, 1.0) as f32 * rect.width();
+    let to_sec = |x: f32| ((x - rect.left()) / rect.width()).clamp(0.0, 1.0) as f64 * duration_s;
+
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    if let Some((a, b)) = loop_range {
+        let loop_rect =
+            egui::Rect::from_x_y_ranges(to_x(a.min(b))..=to_x(a.max(b)), rect.y_range());
+        painter.rect_filled(
+            loop_rect,
+            0.0,
+            Color32::from_rgba_unmultiplied(120, 180, 255, 48),
+        );
+    }
+
+    // One vertical stroke per pixel column: min..max over the buckets it covers.
+    let mid_y = rect.center().y;
+    let half_h = rect.height() * 0.5;
+    let wave_stroke = Stroke::new(1.0, ui.visuals().weak_text_color());
+    for col in 0..columns {
+        let b0 = col * n / columns;
+        let b1 = ((col + 1) * n / columns).max(b0 + 1).min(n);
+        let (lo, hi) = (b0..b1).fold((0.0f32, 0.0f32), |(lo, hi), i| {
+            let p = level.mono(i);
+            (lo.min(p.min), hi.max(p.max))
+        });
+        let x = rect.left() + col as f32 + 0.5;
+        let top = mid_y - hi.clamp(-1.0, 1.0) * half_h;
+        let bottom = mid_y - lo.clamp(-1.0, 1.0) * half_h;
+        painter.line_segment([egui::pos2(x, top), egui::pos2(x, bottom)], wave_stroke);
+    }
+
+    let window = egui::Rect::from_x_y_ranges(
+        to_x(x_min)..=to_x(x_max).max(to_x(x_min) + 2.0),
+        rect.y_range(),
+    );
+    painter.rect_filled(window, 0.0, Color32::from_white_alpha(24));
+    painter.rect_stroke(
+        window,
+        0.0,
+        Stroke::new(1.0, ui.visuals().strong_text_color()),
+        StrokeKind::Inside,
+    );
+
+    if let Some(t) = playhead_sec {
+        painter.vline(to_x(t), rect.y_range(), Stroke::new(1.0, Color32::WHITE));
+    }
+
+    let span = x_max - x_min;
+    let mut view = None;
+    if let Some(pos) = response.interact_pointer_pos() {
+        if response.drag_started_by(PointerButton::Primary)
+            || response.clicked_by(PointerButton::Primary)
+        {
+            // Grabbing outside the window recenters it under the pointer first.
+            if !window.contains(pos) {
+                let start = to_sec(pos.x) - span * 0.5;
+                view = Some((start, start + span));
+            }
+        } else if response.dragged_by(PointerButton::Primary) {
+            let delta = response.drag_delta().x as f64 / rect.width() as f64 * duration_s;
+            view = Some((x_min + delta, x_max + delta));
+        }
+    }
+
+    OverviewResult {
+        view: view.map(|(start, end)| {
+            let shift = if start < 0.0 {
+                -start
+            } else if end > duration_s {
+                duration_s - end
+            } else {
+                0.0
+            };
+            (start + shift, end + shift)
+        }),
+    }
+}
@@ -0,0 +1,233 @@
+use eframe::egui;
+use eframe::egui::{Color32, ColorImage, TextureHandle, TextureOptions};
+use egui_plot::{GridMark, Line, PlotBounds, PlotImage, PlotPoint};
+use std::sync::Arc;
+
+use crate::analysis::notes::note_name;
+use crate::analysis::pitch::PitchTrack;
+use crate::analysis::spectrogram::{HIGH_MIDI, LOW_MIDI, ROWS_PER_SEMITONE, Spectrogram};
+use crate::ui::waveform::{
+    WaveformResult, draw_loop_overlay, draw_playhead, interaction_result, timeline_plot,
+};
+
+/// Inputs for one spectrogram frame; X bounds are shared with the waveform.
+pub struct SpectrogramView<'a> {
+    pub spec: &'a Arc<Spectrogram>,
+    pub duration_s: f64,
+    pub x_min: f64,
+    pub x_max: f64,
+    pub playhead_sec: Option<f64>,
+    pub loop_range: Option<(f64, f64)>,
+    pub allow_pan: bool,
+    /// Optional pitch contour drawn on top, in the same MIDI units as the Y axis.
+    pub pitch: Option<&'a PitchTrack>,
+    pub cache: &'a mut SpectrogramCache,
+}
+
+/// Texture for the visible window, rebuilt only when the view or data changes.
+#[derive(Default)]
+pub struct SpectrogramCache {
+    key: Option<CacheKey>,
+    texture: Option<TextureHandle>,
+    /// Time span actually covered by the texture (whole frames).
+    span: (f64, f64),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct CacheKey {
+    x_min: f64,
+    x_max: f64,
+    width_px: f32,
+    spec: usize,
+}
+
+/// Draw a log-frequency spectrogram with note names on the Y axis.
+/// Interaction is reported exactly like the waveform so both drive the same view.
+pub fn draw_spectrogram(ui: &mut egui::Ui, view: SpectrogramView<'_>) -> WaveformResult {
+    let SpectrogramView {
+        spec,
+        duration_s,
+        x_min,
+        x_max,
+        playhead_sec,
+        loop_range,
+        allow_pan,
+        pitch,
+        cache,
+    } = view;
+
+    let shift_down = ui.input(|i| i.modifiers.shift);
+    let key = CacheKey {
+        x_min,
+        x_max,
+        width_px: ui.available_width().max(1.0),
+        spec: Arc::as_ptr(spec) as usize,
+    };
+    if cache.key != Some(key) {
+        let (image, span) = visible_image(spec, x_min, x_max, key.width_px as usize);
+        match &mut cache.texture {
+            Some(tex) => tex.set(image, TextureOptions::LINEAR),
+            None => {
+                cache.texture = Some(ui.ctx().load_texture(
+                    "spectrogram",
+                    image,
+                    TextureOptions::LINEAR,
+                ));
+            }
+        }
+        cache.span = span;
+        cache.key = Some(key);
+    }
+
+    // Row centers sit on MIDI notes; extend half a row so cells are centered.
+    let half_row = 0.5 / ROWS_PER_SEMITONE as f64;
+    let y_min = LOW_MIDI - half_row;
+    let y_max = HIGH_MIDI + half_row;
+    let texture_id = cache.texture.as_ref().map(|t| t.id());
+    let (span_start, span_end) = cache.span;
+
+    let response = timeline_plot("spectrogram_plot", 200.0, allow_pan && !shift_down)
+        .include_y(y_min)
+        .include_y(y_max)
+        .y_grid_spacer(note_grid)
+        .y_axis_formatter(|mark, _range| note_name(mark.value.round() as i32))
+        .show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(PlotBounds::from_min_max([x_min, y_min], [x_max, y_max]));
+
+            if let Some(id) = texture_id
+                && span_end > span_start
+            {
+                let center = PlotPoint::new((span_start + span_end) * 0.5, (y_min + y_max) * 0.5);
+                let size = egui::vec2((span_end - span_start) as f32, (y_max - y_min) as f32);
+                plot_ui.image(PlotImage::new("spectrogram", id, center, size));
+            }
+
+            draw_loop_overlay(plot_ui, loop_range, duration_s, y_min, y_max);
+            if let Some(track) = pitch {
+                draw_pitch_contour(plot_ui, track, x_min, x_max, key.width_px as usize);
+            }
+            draw_playhead(plot_ui, playhead_sec, duration_s);
+
+            plot_ui.plot_bounds()
+        });
+
+    interaction_result(ui, &response, duration_s, shift_down)
+}
+
+/// Voiced stretches of the pitch track as separate lines, thinned to about two
+/// points per pixel.
+fn draw_pitch_contour(
+    plot_ui: &mut egui_plot::PlotUi<'_>,
+    track: &PitchTrack,
+    x_min: f64,
+    x_max: f64,
+    width_px: usize,
+) {
+    let dt = track.frame_seconds;
+    let f0 = ((x_min / dt).floor().max(0.0) as usize).min(track.frames.len());
+    let f1 = ((x_max / dt).ceil().max(0.0) as usize + 1).min(track.frames.len());
+    let stride = ((f1.saturating_sub(f0)) / (width_px.max(1) * 2)).max(1);
+    let color = Color32::from_rgb(80, 230, 255);
+
+    let mut run: Vec<[f64; 2]> = Vec::new();
+    let mut flush = |run: &mut Vec<[f64; 2]>| {
+        if run.len() > 1 {
+            plot_ui.line(
+                Line::new("pitch", std::mem::take(run))
+                    .color(color)
+                    .width(2.0),
+            );
+        }
+        run.clear();
+    };
+    for k in (f0..f1).step_by(stride) {
+        match track.frames[k] {
+            Some(midi) => run.push([k as f64 * dt, midi as f64]),
+            None => flush(&mut run),
+        }
+    }
+    flush(&mut run);
+}
+
+/// Grid lines on every C (labelled) and every A.
+fn note_grid(input: egui_plot::GridInput) -> Vec<GridMark> {
+    let (lo, hi) = input.bounds;
+    let mut marks = Vec::new();
+    let mut midi = lo.ceil() as i32;
+    while (midi as f64) <= hi {
+        match midi.rem_euclid(12) {
+            0 => marks.push(GridMark {
+                value: midi as f64,
+                step_size: 12.0,
+            }),
+            9 => marks.push(GridMark {
+                value: midi as f64,
+                step_size: 6.0,
+            }),
+            _ => {}
+        }
+        midi += 1;
+    }
+    marks
+}
+
+/// Render frames covering `[x_min, x_max]` into at most `width_px` columns,
+/// keeping the loudest frame per column. Returns the image and its time span.
+fn visible_image(
+    spec: &Spectrogram,
+    x_min: f64,
+    x_max: f64,
+    width_px: usize,
+) -> (ColorImage, (f64, f64)) {
+    let dt = spec.frame_seconds();
+    let f0 = ((x_min / dt).floor().max(0.0) as usize).min(spec.frames);
+    let f1 = ((x_max / dt).ceil().max(0.0) as usize + 1).min(spec.frames);
+    let visible = f1.saturating_sub(f0);
+    if visible == 0 || spec.rows == 0 {
+        return (ColorImage::new([1, 1], vec![Color32::BLACK]), (0.0, 0.0));
+    }
+
+    let columns = visible.min(width_px.max(1));
+    let rows = spec.rows;
+    let mut pixels = vec![Color32::BLACK; columns * rows];
+    let mut column = vec![0u8; rows];
+    for c in 0..columns {
+        let a = f0 + c * visible / columns;
+        let b = (f0 + (c + 1) * visible / columns).max(a + 1).min(f1);
+        column.fill(0);
+        for k in a..b {
+            for (dst, src) in column.iter_mut().zip(spec.frame(k)) {
+                *dst = (*dst).max(*src);
+            }
+        }
+        // Image row 0 is the top, i.e. the highest pitch.
+        for (r, v) in column.iter().enumerate() {
+            pixels[(rows - 1 - r) * columns + c] = heat_color(*v);
+        }
+    }
+
+    // Frame k is centered at k·dt, so the covered span is offset by half a frame.
+    let span = ((f0 as f64 - 0.5) * dt, (f1 as f64 - 0.5) * dt);
+    (ColorImage::new([columns, rows], pixels), span)
+}
+
+/// Black → purple → orange → yellow colour map for a quantised level.
+fn heat_color(v: u8) -> Color32 {
+    let t = v as f32 / 255.0;
+    let stops = [
+        (0.0, [0.0, 0.0, 0.0]),
+        (0.35, [0.30, 0.05, 0.45]),
+        (0.65, [0.90, 0.35, 0.10]),
+        (1.0, [1.0, 0.95, 0.55]),
+    ];
+    for w in stops.windows(2) {
+        let (t0, c0) = w[0];
+        let (t1, c1) = w[1];
+        if t <= t1 {
+            let u = ((t - t0) / (t1 - t0)).clamp(0.0, 1.0);
+            let ch = |i: usize| ((c0[i] + (c1[i] - c0[i]) * u) * 255.0) as u8;
+            return Color32::from_rgb(ch(0), ch(1), ch(2));
+        }
+    }
+    Color32::from_rgb(255, 242, 140)
+}
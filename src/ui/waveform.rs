@@ -1,50 +1,147 @@
 use eframe::egui;
 use eframe::egui::{Color32, PointerButton, Stroke};
-use egui_plot::{Line, Plot, PlotBounds, PlotPoints, Polygon, VLine};
+use egui_plot::{
+    Line, Plot, PlotBounds, PlotPoint, PlotPoints, PlotResponse, PlotUi, Polygon, Text, VLine,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-use crate::audio::decode::DecodedInfo;
+use crate::analysis::beats::TempoMap;
+use crate::analysis::structure::Section;
+use crate::audio::decode::{DecodedInfo, MemoryAudio};
+use crate::audio::peaks::{Peak, PeakLevel, PeakPyramid};
+
+/// Width reserved for the Y axis of every timeline plot.
+const TIMELINE_Y_AXIS_WIDTH: f32 = 36.0;
+
+/// Target density of envelope points per pixel column.
+const POINTS_PER_PX: f64 = 2.0;
+
+/// Vertical spacing between lane centers, in plot units (each lane spans [-1, 1]).
+const LANE_SPACING: f64 = 2.0;
+
+/// How channels are laid out in the waveform plot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WaveformLanes {
+    /// All channels summarised in one lane.
+    #[default]
+    Mixed,
+    /// One lane per channel.
+    Channels,
+    /// Mid (L+R) and side (L−R) lanes; stereo only, otherwise same as `Channels`.
+    MidSide,
+}
+
+impl WaveformLanes {
+    pub fn label(self) -> &'static str {
+        match self {
+            WaveformLanes::Mixed => "Mixed",
+            WaveformLanes::Channels => "Lanes",
+            WaveformLanes::MidSide => "Mid/Side",
+        }
+    }
+}
+
+/// Inputs for one waveform frame.
+pub struct WaveformView<'a> {
+    pub info: &'a DecodedInfo,
+    pub audio: Option<&'a MemoryAudio>,
+    pub x_min: f64,
+    pub x_max: f64,
+    pub playhead_sec: Option<f64>,
+    pub loop_range: Option<(f64, f64)>,
+    /// False while the parent is dragging a marker or the playhead.
+    pub allow_pan: bool,
+    pub lanes: WaveformLanes,
+    pub beat_grid: Option<&'a TempoMap>,
+    /// Detected onset times in seconds, ascending.
+    pub onsets: &'a [f64],
+    /// Repeated passages drawn as coloured bands.
+    pub sections: &'a [Section],
+    /// Hot cue positions by slot; slot 0 is labelled 1.
+    pub cues: &'a [Option<f64>],
+    pub cache: &'a mut WaveformCache,
+}
+
+/// Envelope points from the previous frame, reused until the view or data changes.
+#[derive(Default)]
+pub struct WaveformCache {
+    key: Option<CacheKey>,
+    lanes: Vec<Envelope>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct CacheKey {
+    x_min: f64,
+    x_max: f64,
+    width_px: f32,
+    peaks: usize,
+    has_audio: bool,
+    lanes: WaveformLanes,
+}
 
 /// Return value for waveform draw: possibly updated X bounds after user panned.
 pub struct WaveformResult {
     pub x_min: f64,
     pub x_max: f64,
     pub pointer_seconds: Option<f64>,
+    pub clicked: bool,
     pub drag_started: bool,
     pub drag_active: bool,
     pub drag_released: bool,
     pub shift_down: bool,
+    /// Alt held: drags grab the nearest beat line as a warp marker.
+    pub alt_down: bool,
+    /// Ctrl (Cmd on macOS) held: loop markers ignore snapping.
+    pub command_down: bool,
+    /// Multiplicative zoom requested by wheel/pinch while hovered (>1 zooms in).
+    pub zoom_delta: f64,
 }
 
-/// Draw a clamped RMS waveform.
-/// - Pan: drag inside the plot (horizontal only).
-/// - Zoom: managed by parent via passed x_min/x_max (horizontal only).
-/// - Y is fixed to [-1, 1].
+/// Draw filled min/max envelopes with RMS overlaid, one lane per `lanes` source,
+/// using the peak level that matches the zoom.
+/// - Pan: drag inside the plot (horizontal only), unless `allow_pan` is false.
+/// - Zoom: managed by parent via passed x_min/x_max (horizontal only); wheel and
+///   pinch gestures are reported back in `zoom_delta`, anchored at `pointer_seconds`.
+/// - Y is fixed: each lane spans [-1, 1], lanes stacked top to bottom.
 /// - Optional playhead (seconds) draws a vertical marker.
-pub fn draw_waveform(
-    ui: &mut egui::Ui,
-    info: &DecodedInfo,
-    mut x_min: f64,
-    mut x_max: f64,
-    playhead_sec: Option<f64>,
-    loop_range: Option<(f64, f64)>,
-) -> WaveformResult {
-    let n = info.rms_preview.len();
-    if n == 0 {
+/// - Zoomed in past the finest peak level, samples come from `audio` directly.
+pub fn draw_waveform(ui: &mut egui::Ui, view: WaveformView<'_>) -> WaveformResult {
+    let WaveformView {
+        info,
+        audio,
+        mut x_min,
+        mut x_max,
+        playhead_sec,
+        loop_range,
+        allow_pan,
+        lanes,
+        beat_grid,
+        onsets,
+        sections,
+        cues,
+        cache,
+    } = view;
+    if info.total_frames == 0 || info.sample_rate == 0 {
         ui.label("No preview available");
         return WaveformResult {
             x_min: 0.0,
             x_max: 0.0,
             pointer_seconds: None,
+            clicked: false,
             drag_started: false,
             drag_active: false,
             drag_released: false,
             shift_down: false,
+            alt_down: false,
+            command_down: false,
+            zoom_delta: 1.0,
         };
     }
 
     let shift_down = ui.input(|i| i.modifiers.shift);
-    let bucket_dt = 1.0_f64 / 50.0; // ≈20 ms
-    let duration_s = (n as f64) * bucket_dt;
+    let sr = info.sample_rate as f64;
+    let duration_s = info.total_frames as f64 / sr;
 
     // Clamp incoming bounds to file duration.
     x_min = x_min.clamp(0.0, duration_s);
@@ -53,58 +150,354 @@ pub fn draw_waveform(
         x_max = (x_min + 1.0).min(duration_s);
     }
 
-    // Build plot points.
-    let points: PlotPoints = (0..n)
-        .map(|i| [i as f64 * bucket_dt, info.rms_preview[i] as f64])
-        .collect();
+    // Build plot points for the visible window only, reusing last frame's if unchanged.
+    let key = CacheKey {
+        x_min,
+        x_max,
+        width_px: ui.available_width().max(1.0),
+        peaks: Arc::as_ptr(&info.peaks) as usize,
+        has_audio: audio.is_some(),
+        lanes,
+    };
+    if cache.key != Some(key) {
+        cache.lanes = lane_sources(info, lanes)
+            .into_iter()
+            .enumerate()
+            .map(|(k, source)| {
+                let center = -(k as f64) * LANE_SPACING;
+                envelope_points(
+                    info,
+                    audio,
+                    source,
+                    center,
+                    x_min,
+                    x_max,
+                    key.width_px as f64,
+                )
+            })
+            .collect();
+        cache.key = Some(key);
+    }
+    let lane_count = cache.lanes.len().max(1);
+    let y_min = -1.0 - (lane_count - 1) as f64 * LANE_SPACING;
+    let y_max = 1.0;
 
-    let line = Line::new("RMS", points);
+    let envelope_color = Color32::from_rgb(150, 150, 160);
+    let rms_color = Color32::from_rgb(220, 220, 230);
+    let mut lines = Vec::new();
+    let mut labels = Vec::new();
+    for env in &cache.lanes {
+        lines.push(
+            Line::new("Peak", PlotPoints::Borrowed(&env.max))
+                .color(envelope_color)
+                .fill(env.center as f32)
+                .fill_alpha(0.6),
+        );
+        lines.push(
+            Line::new("Peak", PlotPoints::Borrowed(&env.min))
+                .color(envelope_color)
+                .fill(env.center as f32)
+                .fill_alpha(0.6),
+        );
+        lines.push(
+            Line::new("RMS", PlotPoints::Borrowed(&env.rms_hi))
+                .color(rms_color)
+                .fill(env.center as f32)
+                .fill_alpha(0.5),
+        );
+        lines.push(
+            Line::new("RMS", PlotPoints::Borrowed(&env.rms_lo))
+                .color(rms_color)
+                .fill(env.center as f32)
+                .fill_alpha(0.5),
+        );
+        if lane_count > 1 {
+            labels.push((env.center + 0.75, env.label.clone()));
+        }
+    }
 
     // Build the plot and read back the (possibly) panned bounds.
-    let response = Plot::new("waveform_plot")
-        .height(180.0)
-        .allow_boxed_zoom(false) // disable box zoom
-        .allow_scroll(false) // disable wheel zoom (we manage zoom externally)
-        .allow_drag([!shift_down, false]) // disable drag while selecting loop
-        .include_y(-1.0)
-        .include_y(1.0)
+    let plot_height = if lane_count > 1 {
+        110.0 * lane_count as f32
+    } else {
+        180.0
+    };
+    let response = timeline_plot("waveform_plot", plot_height, allow_pan && !shift_down)
+        .include_y(y_min)
+        .include_y(y_max)
         .include_x(0.0)
         .include_x(duration_s)
         .show(ui, |plot_ui| {
             // Set starting bounds from parent state.
-            let start_bounds = PlotBounds::from_min_max([x_min, -1.0], [x_max, 1.0]);
+            let start_bounds = PlotBounds::from_min_max([x_min, y_min], [x_max, y_max]);
             plot_ui.set_plot_bounds(start_bounds);
 
-            if let Some((a, b)) = loop_range {
-                let start = a.min(b).clamp(0.0, duration_s);
-                let end = b.max(a).clamp(0.0, duration_s);
-                if end > start {
-                    let fill_points: PlotPoints =
-                        vec![[start, -1.0], [start, 1.0], [end, 1.0], [end, -1.0]].into();
-                    let color = Color32::from_rgba_unmultiplied(120, 180, 255, 48);
-                    let polygon = Polygon::new("loop_range_fill", fill_points)
-                        .fill_color(color)
-                        .stroke(Stroke::NONE);
-                    plot_ui.polygon(polygon);
-                }
-                let marker_color = Color32::from_rgb(120, 180, 255);
-                plot_ui.vline(VLine::new("loop_start", start).color(marker_color));
-                plot_ui.vline(VLine::new("loop_end", end).color(marker_color));
+            draw_section_bands(plot_ui, sections, x_min, x_max, (y_min, y_max));
+            draw_loop_overlay(plot_ui, loop_range, duration_s, y_min, y_max);
+            if let Some(grid) = beat_grid {
+                draw_beat_grid(plot_ui, grid, x_min, x_max, key.width_px, y_max);
             }
+            draw_onset_ticks(plot_ui, onsets, x_min, x_max, key.width_px, (y_min, y_max));
 
             // Draw waveform.
-            plot_ui.line(line);
+            for line in lines {
+                plot_ui.line(line);
+            }
+            for (y, label) in labels {
+                let pos = PlotPoint::new(x_min, y);
+                plot_ui.text(Text::new("lane_label", pos, label).anchor(egui::Align2::LEFT_CENTER));
+            }
+
+            draw_cue_flags(plot_ui, cues, x_min, x_max, (y_min, y_max));
 
             // Optional playhead.
-            if let Some(t) = playhead_sec {
-                let clamped = t.clamp(0.0, duration_s);
-                plot_ui.vline(VLine::new("playhead", clamped));
-            }
+            draw_playhead(plot_ui, playhead_sec, duration_s);
 
             // Return current (after-user-pan) bounds.
             plot_ui.plot_bounds()
         });
 
+    interaction_result(ui, &response, duration_s, shift_down)
+}
+
+/// Plot preconfigured for a time axis whose X range is owned by the parent.
+/// All timeline plots share the Y-axis width so their time axes line up.
+pub fn timeline_plot(id: &str, height: f32, allow_pan: bool) -> Plot<'_> {
+    Plot::new(id)
+        .height(height)
+        .y_axis_min_width(TIMELINE_Y_AXIS_WIDTH)
+        .allow_boxed_zoom(false) // disable box zoom
+        .allow_zoom(false) // pinch/ctrl-wheel reported via `zoom_delta` instead
+        .allow_scroll(false) // disable wheel zoom (we manage zoom externally)
+        .allow_drag([allow_pan, false]) // disable drag while selecting loop
+}
+
+/// Shaded loop region with A/B marker lines.
+pub fn draw_loop_overlay(
+    plot_ui: &mut PlotUi<'_>,
+    loop_range: Option<(f64, f64)>,
+    duration_s: f64,
+    y_min: f64,
+    y_max: f64,
+) {
+    let Some((a, b)) = loop_range else {
+        return;
+    };
+    let start = a.min(b).clamp(0.0, duration_s);
+    let end = b.max(a).clamp(0.0, duration_s);
+    if end > start {
+        let fill_points: PlotPoints =
+            vec![[start, y_min], [start, y_max], [end, y_max], [end, y_min]].into();
+        let color = Color32::from_rgba_unmultiplied(120, 180, 255, 48);
+        let polygon = Polygon::new("loop_range_fill", fill_points)
+            .fill_color(color)
+            .stroke(Stroke::NONE);
+        plot_ui.polygon(polygon);
+    }
+    let marker_color = Color32::from_rgb(120, 180, 255);
+    plot_ui.vline(VLine::new("loop_start", start).color(marker_color));
+    plot_ui.vline(VLine::new("loop_end", end).color(marker_color));
+}
+
+/// Colour of a section group, shared by the bands and the loop suggestions.
+pub fn section_color(group: usize) -> Color32 {
+    const PALETTE: [(u8, u8, u8); 6] = [
+        (240, 110, 90),
+        (90, 190, 120),
+        (220, 190, 70),
+        (170, 120, 230),
+        (70, 190, 210),
+        (230, 130, 190),
+    ];
+    let (r, g, b) = PALETTE[group % PALETTE.len()];
+    Color32::from_rgb(r, g, b)
+}
+
+/// Faint full-height tint per section, labelled along the bottom edge.
+fn draw_section_bands(
+    plot_ui: &mut PlotUi<'_>,
+    sections: &[Section],
+    x_min: f64,
+    x_max: f64,
+    (y_min, y_max): (f64, f64),
+) {
+    for section in sections {
+        if section.end < x_min || section.start > x_max {
+            continue;
+        }
+        let color = section_color(section.group);
+        let fill = Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), 22);
+        plot_ui.polygon(
+            Polygon::new(
+                "section",
+                vec![
+                    [section.start, y_min],
+                    [section.start, y_max],
+                    [section.end, y_max],
+                    [section.end, y_min],
+                ],
+            )
+            .fill_color(fill)
+            .stroke(Stroke::NONE),
+        );
+        let x = section.start.max(x_min);
+        let y = y_min + (y_max - y_min) * 0.1;
+        plot_ui.text(
+            Text::new(
+                "section_label",
+                PlotPoint::new(x, y),
+                egui::RichText::new(&section.label).color(color),
+            )
+            .anchor(egui::Align2::LEFT_CENTER),
+        );
+    }
+}
+
+/// A numbered flag per hot cue, hanging from the top edge.
+fn draw_cue_flags(
+    plot_ui: &mut PlotUi<'_>,
+    cues: &[Option<f64>],
+    x_min: f64,
+    x_max: f64,
+    (y_min, y_max): (f64, f64),
+) {
+    let color = Color32::from_rgb(255, 210, 70);
+    let width = (x_max - x_min) * 0.012;
+    let height = (y_max - y_min) * 0.12;
+    for (slot, t) in cues.iter().enumerate() {
+        let Some(t) = *t else {
+            continue;
+        };
+        if t < x_min || t > x_max {
+            continue;
+        }
+        plot_ui.vline(VLine::new("cue", t).color(color).width(1.0));
+        plot_ui.polygon(
+            Polygon::new(
+                "cue_flag",
+                vec![
+                    [t, y_max],
+                    [t + width, y_max - height * 0.5],
+                    [t, y_max - height],
+                ],
+            )
+            .fill_color(color)
+            .stroke(Stroke::NONE),
+        );
+        plot_ui.text(
+            Text::new(
+                "cue_label",
+                PlotPoint::new(t + width, y_max - height * 0.5),
+                egui::RichText::new((slot + 1).to_string())
+                    .color(color)
+                    .strong(),
+            )
+            .anchor(egui::Align2::LEFT_CENTER),
+        );
+    }
+}
+
+/// Beat and bar lines with a bars:beats ruler along the top edge. Lines and
+/// labels thin out as they get denser than a few pixels apart; warp markers are
+/// drawn on top.
+pub fn draw_beat_grid(
+    plot_ui: &mut PlotUi<'_>,
+    grid: &TempoMap,
+    x_min: f64,
+    x_max: f64,
+    width_px: f32,
+    y_max: f64,
+) {
+    let span = x_max - x_min;
+    if span <= 0.0 {
+        return;
+    }
+    // Density follows the local tempo at the view center.
+    let beat_seconds = grid.beat_seconds_at((x_min + x_max) * 0.5);
+    let px_per_beat = width_px as f64 * beat_seconds / span;
+    let px_per_bar = px_per_beat * grid.beats_per_bar.max(1) as f64;
+    // Label every n-th bar, n a power of two, so labels stay ~48px apart.
+    let mut label_every = 1i64;
+    while (label_every as f64) * px_per_bar < 48.0 && label_every < 1 << 20 {
+        label_every *= 2;
+    }
+
+    let bar_color = Color32::from_rgba_unmultiplied(255, 255, 255, 70);
+    let beat_color = Color32::from_rgba_unmultiplied(255, 255, 255, 25);
+    let downbeat_color = Color32::from_rgb(255, 170, 60);
+    for (i, t) in grid.beats_in(x_min, x_max) {
+        let (bar, beat) = grid.bar_beat(i);
+        let is_bar = beat == 1;
+        if i == 0 {
+            // The anchor is draggable, so make it stand out.
+            plot_ui.vline(VLine::new("downbeat", t).color(downbeat_color).width(1.5));
+        } else if is_bar && (px_per_bar >= 4.0 || (bar - 1).rem_euclid(label_every) == 0) {
+            plot_ui.vline(VLine::new("bar", t).color(bar_color));
+        } else if !is_bar && px_per_beat >= 8.0 {
+            plot_ui.vline(VLine::new("beat", t).color(beat_color));
+        }
+
+        let label = if is_bar && (bar - 1).rem_euclid(label_every) == 0 {
+            Some(bar.to_string())
+        } else if !is_bar && px_per_beat >= 40.0 {
+            Some(format!("{bar}:{beat}"))
+        } else {
+            None
+        };
+        if let Some(label) = label {
+            let pos = PlotPoint::new(t, y_max);
+            plot_ui.text(
+                Text::new("ruler", pos, egui::RichText::new(label).small())
+                    .anchor(egui::Align2::LEFT_TOP),
+            );
+        }
+    }
+    let warp_color = Color32::from_rgb(230, 90, 200);
+    for (_, t) in grid.warps().filter(|(_, t)| (x_min..=x_max).contains(t)) {
+        plot_ui.vline(VLine::new("warp", t).color(warp_color).width(1.5));
+    }
+}
+
+/// Short faint ticks along the bottom edge at each visible onset; skipped when
+/// they would be packed closer than a few pixels.
+fn draw_onset_ticks(
+    plot_ui: &mut PlotUi<'_>,
+    onsets: &[f64],
+    x_min: f64,
+    x_max: f64,
+    width_px: f32,
+    (y_min, y_max): (f64, f64),
+) {
+    let a = onsets.partition_point(|t| *t < x_min);
+    let b = onsets.partition_point(|t| *t <= x_max);
+    if b <= a || (b - a) as f32 > width_px / 3.0 {
+        return;
+    }
+    let tick = (y_max - y_min) * 0.06;
+    let color = Color32::from_rgba_unmultiplied(255, 255, 255, 60);
+    for &t in &onsets[a..b] {
+        plot_ui.line(
+            Line::new("onset", vec![[t, y_min], [t, y_min + tick]])
+                .color(color)
+                .width(1.0),
+        );
+    }
+}
+
+pub fn draw_playhead(plot_ui: &mut PlotUi<'_>, playhead_sec: Option<f64>, duration_s: f64) {
+    if let Some(t) = playhead_sec {
+        let clamped = t.clamp(0.0, duration_s);
+        plot_ui.vline(VLine::new("playhead", clamped));
+    }
+}
+
+/// Read back the (possibly panned) X bounds and pointer interaction of a timeline plot.
+pub fn interaction_result(
+    ui: &egui::Ui,
+    response: &PlotResponse<PlotBounds>,
+    duration_s: f64,
+    shift_down: bool,
+) -> WaveformResult {
     // Clamp to duration and fixed Y.
     let b = response.inner;
     let nx_min = b.min()[0].clamp(0.0, duration_s);
@@ -115,13 +508,209 @@ pub fn draw_waveform(
         .or_else(|| response.response.hover_pos())
         .map(|pos| response.transform.value_from_position(pos).x);
 
+    let zoom_delta = if response.response.hovered() {
+        ui.input(|i| {
+            // Plain wheel zooms too; ctrl+wheel and pinch already arrive as zoom_delta.
+            let wheel = (i.smooth_scroll_delta.y as f64 / 200.0).exp();
+            i.zoom_delta() as f64 * wheel
+        })
+    } else {
+        1.0
+    };
+
     WaveformResult {
         x_min: nx_min,
         x_max: nx_max,
         pointer_seconds,
+        clicked: response.response.clicked_by(PointerButton::Primary),
         drag_started: response.response.drag_started_by(PointerButton::Primary),
         drag_active: response.response.dragged_by(PointerButton::Primary),
         drag_released: response.response.drag_stopped_by(PointerButton::Primary),
         shift_down,
+        alt_down: ui.input(|i| i.modifiers.alt),
+        command_down: ui.input(|i| i.modifiers.command),
+        zoom_delta,
+    }
+}
+
+/// Where one lane's samples come from.
+#[derive(Clone, Copy)]
+enum LaneSource {
+    Mixed,
+    Channel(usize),
+    Mid,
+    Side,
+}
+
+impl LaneSource {
+    fn label(self) -> String {
+        match self {
+            LaneSource::Mixed => "Mix".to_string(),
+            LaneSource::Channel(0) => "L".to_string(),
+            LaneSource::Channel(1) => "R".to_string(),
+            LaneSource::Channel(c) => format!("Ch {}", c + 1),
+            LaneSource::Mid => "Mid".to_string(),
+            LaneSource::Side => "Side".to_string(),
+        }
+    }
+
+    fn pyramid(self, info: &DecodedInfo) -> &PeakPyramid {
+        match self {
+            LaneSource::Mid | LaneSource::Side => info.mid_side.as_deref().unwrap_or(&info.peaks),
+            _ => &info.peaks,
+        }
+    }
+
+    fn bucket(self, level: &PeakLevel, i: usize) -> Peak {
+        match self {
+            LaneSource::Mixed => level.mono(i),
+            LaneSource::Channel(c) => level.channels[c][i],
+            LaneSource::Mid => level.channels[0][i],
+            LaneSource::Side => level.channels[1][i],
+        }
+    }
+
+    fn sample(self, audio: &MemoryAudio, f: usize) -> f32 {
+        let ch = audio.channels as usize;
+        let frame = &audio.data[f * ch..(f + 1) * ch];
+        match self {
+            LaneSource::Mixed => frame.iter().sum::<f32>() / ch as f32,
+            LaneSource::Channel(c) => frame[c],
+            LaneSource::Mid => (frame[0] + frame[1]) * 0.5,
+            LaneSource::Side => (frame[0] - frame[1]) * 0.5,
+        }
+    }
+}
+
+fn lane_sources(info: &DecodedInfo, lanes: WaveformLanes) -> Vec<LaneSource> {
+    let channels = info.channels as usize;
+    match lanes {
+        WaveformLanes::MidSide if channels == 2 && info.mid_side.is_some() => {
+            vec![LaneSource::Mid, LaneSource::Side]
+        }
+        WaveformLanes::Channels | WaveformLanes::MidSide if channels > 1 => {
+            (0..channels).map(LaneSource::Channel).collect()
+        }
+        _ => vec![LaneSource::Mixed],
+    }
+}
+
+/// One lane's envelope lines in seconds/amplitude, already offset to `center`.
+#[derive(Default)]
+struct Envelope {
+    label: String,
+    center: f64,
+    max: Vec<PlotPoint>,
+    min: Vec<PlotPoint>,
+    rms_hi: Vec<PlotPoint>,
+    rms_lo: Vec<PlotPoint>,
+}
+
+impl Envelope {
+    fn push(&mut self, t: f64, p: Peak) {
+        let c = self.center;
+        self.max.push(PlotPoint::new(t, c + p.max as f64));
+        self.min.push(PlotPoint::new(t, c + p.min as f64));
+        self.rms_hi.push(PlotPoint::new(t, c + p.rms as f64));
+        self.rms_lo.push(PlotPoint::new(t, c - p.rms as f64));
+    }
+}
+
+/// Min/max/RMS points for one lane covering `[x_min, x_max]`, decimated to about
+/// `POINTS_PER_PX` per pixel column. Points sit on a grid anchored at t=0 so
+/// panning doesn't make the envelope shimmer. Data comes from the finest pyramid
+/// level that is still coarser than one point, or from raw samples once zoomed in
+/// past the finest level.
+fn envelope_points(
+    info: &DecodedInfo,
+    audio: Option<&MemoryAudio>,
+    source: LaneSource,
+    center: f64,
+    x_min: f64,
+    x_max: f64,
+    width_px: f64,
+) -> Envelope {
+    let sr = info.sample_rate as f64;
+    let total = info.total_frames as usize;
+    let mut env = Envelope {
+        label: source.label(),
+        center,
+        ..Default::default()
+    };
+
+    let pyramid = source.pyramid(info);
+    let frames_per_point = (x_max - x_min) * sr / (width_px * POINTS_PER_PX);
+    let level = pyramid.level_for(frames_per_point);
+
+    if let (None, Some(audio)) = (level, audio) {
+        if audio.channels == 0 {
+            return env;
+        }
+        let total = total.min(audio.frames as usize);
+        if frames_per_point <= 1.0 {
+            // Fewer samples than points: plot every sample.
+            let f0 = ((x_min * sr).floor() as usize).saturating_sub(1);
+            let f1 = ((x_max * sr).ceil() as usize + 2).min(total);
+            for f in f0..f1 {
+                let v = source.sample(audio, f);
+                env.push(
+                    f as f64 / sr,
+                    Peak {
+                        min: v,
+                        max: v,
+                        rms: 0.0,
+                    },
+                );
+            }
+            return env;
+        }
+        let p0 = ((x_min * sr / frames_per_point).floor() as usize).saturating_sub(1);
+        let p1 = (x_max * sr / frames_per_point).ceil() as usize + 1;
+        for p in p0..p1 {
+            let f0 = (p as f64 * frames_per_point) as usize;
+            let f1 = (((p + 1) as f64 * frames_per_point) as usize).min(total);
+            if f0 >= f1 {
+                break;
+            }
+            let mut peak = Peak {
+                min: f32::INFINITY,
+                max: f32::NEG_INFINITY,
+                rms: 0.0,
+            };
+            let mut sq = 0.0f64;
+            for f in f0..f1 {
+                let v = source.sample(audio, f);
+                peak.min = peak.min.min(v);
+                peak.max = peak.max.max(v);
+                sq += (v as f64) * (v as f64);
+            }
+            peak.rms = (sq / (f1 - f0) as f64).sqrt() as f32;
+            env.push((f0 + f1) as f64 * 0.5 / sr, peak);
+        }
+        return env;
+    }
+
+    let Some(level) = level.or(pyramid.levels.first()) else {
+        return env;
+    };
+    let bf = level.bucket_frames as f64;
+    // Never step finer than one bucket, even when falling back without raw audio.
+    let step = frames_per_point.max(bf);
+    let p0 = ((x_min * sr / step).floor() as usize).saturating_sub(1);
+    let p1 = (x_max * sr / step).ceil() as usize + 1;
+    let mut buckets = Vec::new();
+    for p in p0..p1 {
+        let b0 = (p as f64 * step / bf) as usize;
+        let b1 = (((p + 1) as f64 * step / bf) as usize)
+            .max(b0 + 1)
+            .min(level.len());
+        if b0 >= b1 {
+            break;
+        }
+        buckets.clear();
+        buckets.extend((b0..b1).map(|i| source.bucket(level, i)));
+        let t = (b0 + b1) as f64 * 0.5 * bf / sr;
+        env.push(t, Peak::merge(&buckets));
     }
+    env
 }